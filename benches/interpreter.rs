@@ -0,0 +1,43 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use raven::Interpreter;
+
+const FIB: &str = "\
+fn fib(n) {
+    if n < 2 {
+        return n
+    }
+    fib(n - 1) + fib(n - 2)
+}
+fib(20)";
+
+const LOOP_SUM: &str = "\
+let total = 0
+let i = 0
+while i < 20000 {
+    total += i
+    i++
+}
+for n in 0..20000 {
+    total += n
+}
+total";
+
+const STRINGS: &str = "\
+let text = \"\"
+for i in 0..2000 {
+    text += str(i) + \",\"
+}
+len(split(text, \",\"))";
+
+/// each script on both engines, in a fresh interpreter every time
+fn scripts(c: &mut Criterion){
+    for (name, code) in [("fib", FIB), ("loop sum", LOOP_SUM), ("string building", STRINGS)] {
+        let mut group = c.benchmark_group(name);
+        group.bench_function("walker", |b| b.iter(|| Interpreter::with_stdlib().run(code.to_string(), false).unwrap()));
+        group.bench_function("vm", |b| b.iter(|| Interpreter::with_stdlib().runVM(code.to_string(), false).unwrap()));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, scripts);
+criterion_main!(benches);
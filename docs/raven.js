@@ -0,0 +1,27 @@
+// loads the module `make build-wasm` puts next to this file and wraps its exports:
+//
+//     const raven = await load("raven.wasm");
+//     const { stdout, result, error } = raven.run('print("hi")\n1 + 2');
+//
+// `bytes` can also be the module itself, as an ArrayBuffer or typed array, where fetch is not around
+export async function load(bytes) {
+    if (typeof bytes === "string" || bytes instanceof URL) {
+        bytes = await (await fetch(bytes)).arrayBuffer();
+    }
+    const { instance } = await WebAssembly.instantiate(bytes, { env: { raven_random: Math.random } });
+    const { memory, raven_alloc, raven_free, raven_run, raven_output_len } = instance.exports;
+
+    return {
+        // runs `source` in a fresh interpreter, giving back {stdout, result, error}
+        run(source) {
+            const encoded = new TextEncoder().encode(source);
+            const pointer = raven_alloc(encoded.length);
+            new Uint8Array(memory.buffer, pointer, encoded.length).set(encoded);
+            const output = raven_run(pointer, encoded.length);
+            raven_free(pointer, encoded.length);
+            // running may have grown the memory, so the view is taken afterwards
+            const json = new Uint8Array(memory.buffer, output, raven_output_len());
+            return JSON.parse(new TextDecoder().decode(json));
+        },
+    };
+}
@@ -0,0 +1,75 @@
+pub const USAGE: &str = "\
+usage: raven [options] <command> [file] [args...]
+
+commands:
+    run <file> [args...]    run a script, passing it the arguments that follow
+    check <file>            parse a script and report syntax errors without running it
+    fmt <file>              print a script in the canonical style
+    ast <file>              dump the tree a script parses into
+    repl                    start an interactive session
+    help                    show this message
+
+options:
+    --debug                 print the parsed script before running it
+    --vm                    run on the bytecode vm instead of the tree-walking interpreter";
+
+#[derive(Debug, PartialEq)]
+pub enum Command{
+    Run{file: String, args: Vec<String>},
+    Check{file: String},
+    Fmt{file: String},
+    Ast{file: String},
+    Repl,
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Options{
+    pub command: Command,
+    pub debug: bool,
+    pub vm: bool,
+}
+
+/// reads the command line, without the program name. options may come anywhere before the
+/// file, everything after a script's file is left for the script
+pub fn parse(arguments: impl IntoIterator<Item = String>) -> Result<Options, String>{
+    let mut arguments = arguments.into_iter();
+    let (mut debug, mut vm) = (false, false);
+    let mut command = None;
+    let mut file = None;
+
+    for argument in arguments.by_ref() {
+        match argument.as_str() {
+            "--debug" => debug = true,
+            "--vm" => vm = true,
+            "-h" | "--help" => command = Some("help".to_string()),
+            flag if flag.starts_with('-') => return Err(format!("unknown option `{}`", flag)),
+            _ if command.is_none() => command = Some(argument),
+            _ => {
+                file = Some(argument);
+                break
+            }
+        }
+    }
+
+    let mut rest = arguments.collect::<Vec<_>>();
+    let needs = |name: &str| file.clone().ok_or_else(|| format!("`{}` needs a file", name));
+    let command = match command.as_deref() {
+        Some("run") => Command::Run { file: needs("run")?, args: std::mem::take(&mut rest) },
+        Some("check") => Command::Check { file: needs("check")? },
+        Some("fmt") => Command::Fmt { file: needs("fmt")? },
+        Some("ast") => Command::Ast { file: needs("ast")? },
+        Some("repl") => Command::Repl,
+        Some("help") | None => Command::Help,
+        Some(other) => return Err(format!("unknown command `{}`", other)),
+    };
+    let extra = match command {
+        // the repl takes no file, so whatever was read as one is extra
+        Command::Repl | Command::Help => file.as_ref().or(rest.first()),
+        _ => rest.first()
+    };
+    if let Some(extra) = extra {
+        return Err(format!("unexpected argument `{}`", extra))
+    }
+    Ok(Options { command, debug, vm })
+}
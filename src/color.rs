@@ -0,0 +1,26 @@
+//! the colours errors, the repl and `ast` dumps are printed in. with the `color` feature these come from
+//! `colored`, without it the same methods hand the text back untouched
+
+#[cfg(feature = "color")]
+pub use colored::Colorize;
+
+/// turns colours off for good, for output that does not end up on a terminal
+pub fn disable(){
+    #[cfg(feature = "color")]
+    colored::control::set_override(false);
+}
+
+#[cfg(not(feature = "color"))]
+macro_rules! plain {
+    ($($name:ident),*) => {
+        /// stands in for `colored::Colorize`
+        pub trait Colorize{
+            $(fn $name(self) -> String;)*
+        }
+        impl Colorize for &str{
+            $(fn $name(self) -> String{ self.to_string() })*
+        }
+    };
+}
+#[cfg(not(feature = "color"))]
+plain!(red, green, blue, yellow, cyan, magenta, purple, bright_black, bold, clear);
@@ -0,0 +1,286 @@
+use std::collections::BTreeMap;
+
+use crate::parser::parser::Type;
+use super::error::{RavenError, RavenResult};
+use super::interpreter::{FunctionTypes, Object, RefScope, Scope};
+
+/// a rust value that can be read out of a raven object, used for the parameters of `Interpreter::register`
+pub trait FromRaven: Sized{
+    fn fromRaven(object: &Object) -> RavenResult<Self>;
+
+    /// the value for an argument left out of the call, `None` when it has to be given
+    fn missing() -> Option<Self>{
+        None
+    }
+}
+
+/// a rust value that can be handed to raven, used for what `Interpreter::register`ed functions return.
+/// `None` is void, which is what `()` turns into
+pub trait IntoRaven{
+    fn intoRaven(self) -> RavenResult<Option<Object>>;
+}
+
+/// the arguments of a call that starts on the rust side: a tuple of values that are `IntoRaven`, or a `Vec` of them
+pub trait IntoArguments{
+    fn intoArguments(self) -> RavenResult<Vec<Object>>;
+}
+
+/// a raven function held on the rust side, so builtins can take callbacks
+#[derive(Debug, Clone)]
+pub struct Callback(Object);
+
+impl Callback{
+    pub fn call<R: FromRaven>(&self, arguments: impl IntoArguments) -> RavenResult<R>{
+        match invoke(&self.0, arguments.intoArguments()?, Scope::new())? {
+            Some(result) => R::fromRaven(&result),
+            None => R::missing().ok_or_else(|| RavenError::VoidValue("a callback's result".to_string())),
+        }
+    }
+}
+
+/// calls `function` from rust. `scope` is only seen by builtins, as the scope they were called from
+pub(crate) fn invoke(function: &Object, arguments: Vec<Object>, scope: RefScope) -> RavenResult<Option<Object>>{
+    match &*function.borrow() {
+        Type::Function(callable) => FunctionTypes::call(callable, function.clone(), arguments, scope),
+        other => Err(RavenError::NotCallable(other.toString())),
+    }
+}
+
+fn argumentObject<T: IntoRaven>(value: T) -> RavenResult<Object>{
+    value.intoRaven()?.ok_or_else(|| RavenError::VoidValue("an argument".to_string()))
+}
+
+impl<T: IntoRaven> IntoArguments for Vec<T>{
+    fn intoArguments(self) -> RavenResult<Vec<Object>>{
+        self.into_iter().map(argumentObject).collect()
+    }
+}
+
+macro_rules! arguments {
+    ($($argument:ident),*) => {
+        impl<$($argument: IntoRaven,)*> IntoArguments for ($($argument,)*){
+            fn intoArguments(self) -> RavenResult<Vec<Object>>{
+                let ($($argument,)*) = self;
+                Ok(vec![$(argumentObject($argument)?),*])
+            }
+        }
+    };
+}
+
+arguments!();
+arguments!(A);
+arguments!(A, B);
+arguments!(A, B, C);
+arguments!(A, B, C, D);
+arguments!(A, B, C, D, E);
+arguments!(A, B, C, D, E, G);
+
+impl FromRaven for Object{
+    fn fromRaven(object: &Object) -> RavenResult<Self>{
+        Ok(object.clone())
+    }
+}
+
+impl FromRaven for f64{
+    fn fromRaven(object: &Object) -> RavenResult<Self>{
+        match &*object.borrow() {
+            Type::Number(number) => Ok(*number as f64),
+            Type::Integer(number) => Ok(*number as f64),
+            other => Err(RavenError::mismatch("use as a number", &[other])),
+        }
+    }
+}
+
+impl FromRaven for i64{
+    fn fromRaven(object: &Object) -> RavenResult<Self>{
+        match &*object.borrow() {
+            Type::Integer(number) => Ok(*number),
+            other => Err(RavenError::mismatch("use as an integer", &[other])),
+        }
+    }
+}
+
+impl FromRaven for bool{
+    fn fromRaven(object: &Object) -> RavenResult<Self>{
+        match &*object.borrow() {
+            Type::Bool(value) => Ok(*value),
+            other => Err(RavenError::mismatch("use as a bool", &[other])),
+        }
+    }
+}
+
+impl FromRaven for String{
+    fn fromRaven(object: &Object) -> RavenResult<Self>{
+        match &*object.borrow() {
+            Type::String(text) => Ok(text.clone()),
+            other => Err(RavenError::mismatch("use as a string", &[other])),
+        }
+    }
+}
+
+impl<T: FromRaven> FromRaven for Vec<T>{
+    fn fromRaven(object: &Object) -> RavenResult<Self>{
+        match &*object.borrow() {
+            Type::List(items) => items.iter().map(T::fromRaven).collect(),
+            other => Err(RavenError::mismatch("use as a list", &[other])),
+        }
+    }
+}
+
+impl<T: FromRaven> FromRaven for BTreeMap<String, T>{
+    fn fromRaven(object: &Object) -> RavenResult<Self>{
+        match &*object.borrow() {
+            Type::Map(entries) => entries.iter().map(|(key, value)| Ok((key.clone(), T::fromRaven(value)?))).collect(),
+            other => Err(RavenError::mismatch("use as a map", &[other])),
+        }
+    }
+}
+
+/// an argument that may be left out, as long as every one after it is too
+impl<T: FromRaven> FromRaven for Option<T>{
+    fn fromRaven(object: &Object) -> RavenResult<Self>{
+        T::fromRaven(object).map(Some)
+    }
+
+    fn missing() -> Option<Self>{
+        Some(None)
+    }
+}
+
+impl FromRaven for Callback{
+    fn fromRaven(object: &Object) -> RavenResult<Self>{
+        match &*object.borrow() {
+            Type::Function(_) => Ok(Callback(object.clone())),
+            other => Err(RavenError::mismatch("call", &[other])),
+        }
+    }
+}
+
+impl IntoRaven for Object{
+    fn intoRaven(self) -> RavenResult<Option<Object>>{
+        Ok(Some(self))
+    }
+}
+
+impl IntoRaven for Type{
+    fn intoRaven(self) -> RavenResult<Option<Object>>{
+        Ok(Some(self.wrap()))
+    }
+}
+
+impl IntoRaven for (){
+    fn intoRaven(self) -> RavenResult<Option<Object>>{
+        Ok(None)
+    }
+}
+
+impl IntoRaven for f64{
+    fn intoRaven(self) -> RavenResult<Option<Object>>{
+        Ok(Some(Type::Number(self as f32).wrap()))
+    }
+}
+
+impl IntoRaven for i64{
+    fn intoRaven(self) -> RavenResult<Option<Object>>{
+        Ok(Some(Type::Integer(self).wrap()))
+    }
+}
+
+impl IntoRaven for bool{
+    fn intoRaven(self) -> RavenResult<Option<Object>>{
+        Ok(Some(Type::Bool(self).wrap()))
+    }
+}
+
+impl IntoRaven for String{
+    fn intoRaven(self) -> RavenResult<Option<Object>>{
+        Ok(Some(Type::String(self).wrap()))
+    }
+}
+
+impl IntoRaven for &str{
+    fn intoRaven(self) -> RavenResult<Option<Object>>{
+        Ok(Some(Type::String(self.to_string()).wrap()))
+    }
+}
+
+impl<T: IntoRaven> IntoRaven for Vec<T>{
+    fn intoRaven(self) -> RavenResult<Option<Object>>{
+        let items = self.into_iter().map(|item| item.intoRaven()?.ok_or_else(|| RavenError::VoidValue("a list element".to_string())));
+        Ok(Some(Type::List(items.collect::<RavenResult<_>>()?).wrap()))
+    }
+}
+
+impl<T: IntoRaven> IntoRaven for BTreeMap<String, T>{
+    fn intoRaven(self) -> RavenResult<Option<Object>>{
+        let entries = self.into_iter().map(|(key, value)| Ok((key, value.intoRaven()?.ok_or_else(|| RavenError::VoidValue("a map value".to_string()))?)));
+        Ok(Some(Type::Map(entries.collect::<RavenResult<_>>()?).wrap()))
+    }
+}
+
+/// `None` is void
+impl<T: IntoRaven> IntoRaven for Option<T>{
+    fn intoRaven(self) -> RavenResult<Option<Object>>{
+        match self {
+            Some(value) => value.intoRaven(),
+            None => Ok(None),
+        }
+    }
+}
+
+impl IntoRaven for Callback{
+    fn intoRaven(self) -> RavenResult<Option<Object>>{
+        Ok(Some(self.0))
+    }
+}
+
+/// failing with an error is how a registered function reports one
+impl<T: IntoRaven> IntoRaven for RavenResult<T>{
+    fn intoRaven(self) -> RavenResult<Option<Object>>{
+        self?.intoRaven()
+    }
+}
+
+/// a rust closure `Interpreter::register` can turn into a builtin. `Arguments` is the tuple of its
+/// parameter types, which only tells the implementations for each number of parameters apart
+pub trait Native<Arguments>: 'static{
+    /// how many arguments a call needs at least and may have at most
+    fn arity() -> (usize, usize);
+    fn invoke(&self, arguments: Vec<Object>) -> RavenResult<Option<Object>>;
+}
+
+fn argument<T: FromRaven>(argument: Option<Object>) -> RavenResult<T>{
+    match argument {
+        Some(argument) => T::fromRaven(&argument),
+        None => Ok(T::missing().expect("the arity check leaves out optional arguments only")),
+    }
+}
+
+macro_rules! native {
+    ($($parameter:ident),*) => {
+        impl<F, R, $($parameter,)*> Native<($($parameter,)*)> for F
+        where F: Fn($($parameter),*) -> R + 'static, R: IntoRaven, $($parameter: FromRaven,)*
+        {
+            fn arity() -> (usize, usize){
+                let required: &[bool] = &[$($parameter::missing().is_none()),*];
+                let least = required.iter().rposition(|required| *required).map_or(0, |last| last + 1);
+                (least, required.len())
+            }
+
+            #[allow(unused_mut, unused_variables)]
+            fn invoke(&self, arguments: Vec<Object>) -> RavenResult<Option<Object>>{
+                let mut arguments = arguments.into_iter();
+                $(let $parameter = argument::<$parameter>(arguments.next())?;)*
+                self($($parameter),*).intoRaven()
+            }
+        }
+    };
+}
+
+native!();
+native!(A);
+native!(A, B);
+native!(A, B, C);
+native!(A, B, C, D);
+native!(A, B, C, D, E);
+native!(A, B, C, D, E, G);
@@ -0,0 +1,70 @@
+use std::cell::Cell;
+
+use super::error::{RavenError, RavenResult};
+
+/// how deep calls may nest unless `Interpreter::set_max_depth` says otherwise
+#[cfg(not(target_arch = "wasm32"))]
+pub const MAX_DEPTH: usize = 10_000;
+/// the stack cannot grow on wasm, so calls have to fit in the megabyte the module starts with
+#[cfg(target_arch = "wasm32")]
+pub const MAX_DEPTH: usize = WASM_MAX_DEPTH;
+
+/// the default on wasm, which `wasm::run` also uses when testing it natively
+pub const WASM_MAX_DEPTH: usize = 200;
+
+thread_local! {
+    /// the calls running right now, on either engine
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    static LIMIT: Cell<usize> = const { Cell::new(MAX_DEPTH) };
+}
+
+/// one call that is still running, it counts towards the depth until dropped
+#[derive(Debug)]
+pub struct Call(());
+
+impl Call{
+    /// counts a new call, or fails with `RavenError::StackOverflow` when that goes past the limit
+    pub fn enter() -> RavenResult<Call>{
+        let depth = DEPTH.with(|depth| depth.get());
+        if depth >= LIMIT.with(|limit| limit.get()) {
+            return Err(RavenError::StackOverflow)
+        }
+        DEPTH.with(|cell| cell.set(depth + 1));
+        Ok(Call(()))
+    }
+}
+
+impl Drop for Call{
+    fn drop(&mut self){
+        DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+/// the limit of one interpreter, in force while it runs and put back to the previous one after
+pub struct Limit(usize);
+
+impl Limit{
+    pub fn set(limit: usize) -> Limit{
+        Limit(LIMIT.with(|cell| cell.replace(limit)))
+    }
+}
+
+impl Drop for Limit{
+    fn drop(&mut self){
+        LIMIT.with(|limit| limit.set(self.0));
+    }
+}
+
+/// runs `f` with room for at least one more call on the native stack, which a new segment is
+/// allocated for when the current one is nearly used up
+#[cfg(not(target_arch = "wasm32"))]
+pub fn grow<R>(f: impl FnOnce() -> R) -> R{
+    // a level of raven calls takes tens of kilobytes of native stack in a debug build
+    stacker::maybe_grow(256 * 1024, 4 * 1024 * 1024, f)
+}
+
+/// the web has no way of growing the stack, wasm traps when it runs out
+#[cfg(target_arch = "wasm32")]
+pub fn grow<R>(f: impl FnOnce() -> R) -> R{
+    f()
+}
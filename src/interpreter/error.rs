@@ -0,0 +1,97 @@
+use std::fmt::Display;
+
+use crate::color::Colorize;
+
+use crate::parser::{parser::Type, span::Span, error::SyntaxError};
+
+pub type RavenResult<T> = Result<T, RavenError>;
+
+/// everything that can go wrong while running a script
+#[derive(Debug, Clone, PartialEq)]
+pub enum RavenError{
+    UndefinedVariable(String),
+    TypeMismatch{operation: String, operands: Vec<String>},
+    ArityMismatch{expected: usize, found: usize},
+    NotCallable(String),
+    /// a statement that produces no value was used where one is required
+    VoidValue(String),
+    Runtime(String),
+    /// calls nested deeper than the interpreter allows
+    StackOverflow,
+    /// a control flow statement such as `return` was used where nothing can handle it
+    Misplaced{keyword: String, context: String},
+    Syntax(SyntaxError),
+    Io(String),
+    /// an error raised while evaluating the node at `Span`
+    At(Span, Box<RavenError>),
+    /// an error raised while loading the module in `file`, whose code is `source`
+    Import{file: String, source: String, error: Box<RavenError>},
+}
+
+impl RavenError{
+    pub fn mismatch(operation: &str, operands: &[&Type]) -> RavenError{
+        let operands = operands.iter().map(|o| o.typeName().to_string()).collect();
+        RavenError::TypeMismatch { operation: operation.to_string(), operands }
+    }
+
+    pub fn missing(key: &str) -> RavenError{
+        RavenError::Runtime(format!("no key \"{}\" in map", key))
+    }
+
+    /// records where the error happened, unless a more precise location is already known
+    pub fn at(self, span: Span) -> RavenError{
+        match self {
+            RavenError::At(..) => self,
+            error => RavenError::At(span, Box::new(error))
+        }
+    }
+
+    /// the error without its location
+    pub fn inner(&self) -> &RavenError{
+        match self {
+            RavenError::At(_, error) => error.inner(),
+            error => error
+        }
+    }
+
+    pub fn span(&self) -> Option<Span>{
+        match self {
+            RavenError::At(span, _) => Some(*span),
+            RavenError::Syntax(error) => Some(error.span),
+            _ => None
+        }
+    }
+
+    /// formats the error for a terminal, with a snippet of `source` when the location is known
+    pub fn render(&self, source: &str, file: &str) -> String{
+        // an error from inside a module is shown in the module's code
+        if let RavenError::Import { file, source, error } = self.inner() {
+            return error.render(source, file)
+        }
+        match self.span() {
+            Some(span) => span.render(source, file, &self.to_string()),
+            None => format!("{}: {}", "error".red().bold(), self.to_string().bold())
+        }
+    }
+}
+
+impl Display for RavenError{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RavenError::UndefinedVariable(name) => write!(f, "cannot find variable `{}`", name),
+            RavenError::TypeMismatch { operation, operands } => write!(f, "cannot {} {}", operation, operands.join(" and ")),
+            RavenError::ArityMismatch { expected, found } => write!(f, "expected {} argument(s) but got {}", expected, found),
+            RavenError::NotCallable(name) => write!(f, "`{}` is not a function", name),
+            RavenError::VoidValue(usage) => write!(f, "cannot use void as {}", usage),
+            RavenError::Runtime(message) => write!(f, "{}", message),
+            RavenError::StackOverflow => write!(f, "stack overflow"),
+            RavenError::Misplaced { keyword, context } => write!(f, "`{}` used outside of a {}", keyword, context),
+            RavenError::Syntax(error) => write!(f, "{}", error),
+            RavenError::Io(message) => write!(f, "{}", message),
+            RavenError::At(_, error) => write!(f, "{}", error),
+            RavenError::Import { file, error, .. } => write!(f, "in {}: {}", file, error),
+        }
+    }
+}
+
+impl std::error::Error for RavenError{}
@@ -1,61 +1,189 @@
-use std::{collections::HashMap, rc::Rc, cell::RefCell};
+use std::{collections::{HashMap, HashSet}, rc::Rc, cell::RefCell};
 use std::fmt::Debug;
 use crate::parser::parser::{Type, ParseString, Func};
+use crate::parser::span::Span;
+use crate::interpreter::error::{RavenError, RavenResult};
+use crate::interpreter::convert::{invoke, IntoArguments, Native};
+use crate::interpreter::depth::{self, Call, Limit, MAX_DEPTH};
+use crate::interpreter::module::Modules;
+use crate::interpreter::resolver::Resolver;
+use crate::interpreter::stdlib;
+use crate::interpreter::value::Value;
+#[cfg(feature = "vm")]
+use crate::vm::{bytecode::Prototype, compiler::Compiler, vm::RavenVM};
 
 pub type Object = Rc<RefCell<Type>>;
 pub type RefScope = Rc<RefCell<Scope>>;
 
+/// why evaluation stopped before reaching the end of a node: an error, or a
+/// statement jumping out towards the function call or loop that handles it.
+/// the jumps carry the span of their statement for the error when nothing handles them
+#[derive(Debug)]
+pub enum Unwind{
+    Error(RavenError),
+    Return(Option<Object>, Span),
+    Break(Span),
+    Continue(Span),
+    /// a call in tail position, handing the body of the function and its frame to the `RunCode` below,
+    /// which runs them in place of its own
+    TailCall(Rc<Vec<Box<Type>>>, RefScope, Span),
+}
+impl From<RavenError> for Unwind{
+    fn from(error: RavenError) -> Self {
+        Unwind::Error(error)
+    }
+}
+impl Unwind{
+    /// for when nothing is left to catch the jump
+    pub fn intoError(self) -> RavenError{
+        let (keyword, context, span) = match self {
+            Unwind::Error(error) => return error,
+            Unwind::Return(_, span) | Unwind::TailCall(.., span) => ("return", "function", span),
+            Unwind::Break(span) => ("break", "loop", span),
+            Unwind::Continue(span) => ("continue", "loop", span),
+        };
+        RavenError::Misplaced { keyword: keyword.to_string(), context: context.to_string() }.at(span)
+    }
+}
+type Flow = Result<Option<Object>, Unwind>;
+
+/// where a variable keeps its value. assigning replaces the object in the slot, so every closure
+/// that sees the variable sees the new value
 #[derive(Debug, PartialEq)]
-pub struct Slot(Object);
+pub struct Slot(RefCell<Object>);
 impl  Slot {
     pub fn new(object: Object) -> Rc<Slot>{
-        Rc::new(Slot(object))
+        Rc::new(Slot(RefCell::new(object)))
     }
-    pub fn set(&mut self, new: Object){
-        self.0 = new;
+    pub fn set(&self, new: Object){
+        *self.0.borrow_mut() = new;
     }
     pub fn get(&self) -> Object{
-        self.0.clone()
+        self.0.borrow().clone()
     }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Scope{
     map: HashMap<String, Rc<Slot>>,
+    /// the variables of a function call or loop iteration, at the indices the `Resolver` gave them.
+    /// a variable declared inside an `if` that did not run has no value yet
+    slots: Vec<Option<Object>>,
     parent: Option<RefScope>
 }
 impl Scope{
     pub fn new() -> RefScope{
-        Rc::new(RefCell::new(Scope { map: HashMap::new(), parent: None }))
+        Rc::new(RefCell::new(Scope { map: HashMap::new(), slots: vec![], parent: None }))
     }
     pub fn with(parent: RefScope) -> RefScope{
-        Rc::new(RefCell::new(Scope { map: HashMap::new(), parent: Some(parent) }))
+        Rc::new(RefCell::new(Scope { map: HashMap::new(), slots: vec![], parent: Some(parent) }))
+    }
+    /// a scope for a function call or loop iteration whose first variables are `slots`
+    pub fn frame(parent: RefScope, slots: Vec<Object>) -> RefScope{
+        let slots = slots.into_iter().map(Some).collect();
+        Rc::new(RefCell::new(Scope { map: HashMap::new(), slots, parent: Some(parent) }))
     }
 
-    pub fn get(&self, key: &str) -> Rc<Slot>{
+    pub fn parent(&self) -> Option<RefScope>{
+        self.parent.clone()
+    }
+
+    pub fn get(&self, key: &str) -> RavenResult<Rc<Slot>>{
         match self.map.get(key){
-            Some(val) => val.clone(),
+            Some(val) => Ok(val.clone()),
             None => {
                 if let Some(parent) = &self.parent{
                     (**parent).borrow().get(key)
                 }else{
-                    panic!("cannot find {}", key)
+                    Err(RavenError::UndefinedVariable(key.to_string()))
                 }
             },
         }
 
     }
+    /// the value of the variable at `index` in the frame `depth` scopes up
+    pub fn local(&self, depth: usize, index: usize) -> Option<Object>{
+        if depth == 0 {
+            return self.slots.get(index).cloned().flatten()
+        }
+        self.parent.as_ref()?.borrow().local(depth - 1, index)
+    }
+    pub fn declareLocal(&mut self, index: usize, value: Object){
+        if index >= self.slots.len() {
+            self.slots.resize(index + 1, None);
+        }
+        self.slots[index] = Some(value);
+    }
+    /// replaces the value of the variable at `index` in the frame `depth` scopes up, false when it has none
+    pub fn assignLocal(&mut self, depth: usize, index: usize, value: Object) -> bool{
+        if depth > 0 {
+            return self.parent.as_ref().is_some_and(|parent| parent.borrow_mut().assignLocal(depth - 1, index, value))
+        }
+        match self.slots.get_mut(index) {
+            Some(slot @ Some(_)) => {
+                *slot = Some(value);
+                true
+            },
+            _ => false
+        }
+    }
+    /// the variables declared directly in this scope, not in its parents
+    pub fn variables(&self) -> Vec<(String, Object)>{
+        self.map.iter().map(|(name, slot)| (name.clone(), slot.get())).collect()
+    }
     pub fn declare(&mut self, key: String, value: Object){
-        self.map.insert(key, Rc::new(Slot(value)));
+        stdlib::binding(&key, &value);
+        self.map.insert(key, Slot::new(value));
+    }
+    /// empties `scope` and its parents, and the scopes captured by the functions they hold. functions point
+    /// back at the scope that holds them, and `Rc` cannot free such cycles on its own
+    fn release(scope: &RefScope, seen: &mut HashSet<usize>){
+        if !seen.insert(Rc::as_ptr(scope) as usize) {
+            return
+        }
+        let (map, slots, parent) = {
+            let mut scope = scope.borrow_mut();
+            (std::mem::take(&mut scope.map), std::mem::take(&mut scope.slots), scope.parent.take())
+        };
+        for slot in map.into_values() {
+            Self::releaseObject(&slot.get(), seen);
+        }
+        for slot in slots.into_iter().flatten() {
+            Self::releaseObject(&slot, seen);
+        }
+        if let Some(parent) = parent {
+            Self::release(&parent, seen);
+        }
     }
-    pub fn assign(&mut self, key: String, value: Object){
-        if let Some(slot) = self.map.get_mut(&key){
-            Rc::get_mut(slot).unwrap().set(value)
+    fn releaseObject(object: &Object, seen: &mut HashSet<usize>){
+        if !seen.insert(Rc::as_ptr(object) as usize) {
+            return
+        }
+        // containers are emptied as well, a list can hold itself just like a function can
+        let (captured, contents) = match &mut *object.borrow_mut() {
+            Type::Function(FunctionTypes::NormalFunction { scope, bound, .. }) => (Some(scope.clone()), std::mem::take(bound)),
+            #[cfg(feature = "vm")]
+            Type::Function(FunctionTypes::Compiled { scope, bound, .. }) => (Some(scope.clone()), std::mem::take(bound)),
+            Type::List(items) => (None, std::mem::take(items)),
+            Type::Map(entries) => (None, std::mem::take(entries).into_values().collect()),
+            _ => return,
+        };
+        contents.iter().for_each(|item| Self::releaseObject(item, seen));
+        if let Some(captured) = captured {
+            Self::release(&captured, seen);
+        }
+    }
+
+    pub fn assign(&self, key: String, value: Object) -> RavenResult<()>{
+        if let Some(slot) = self.map.get(&key){
+            stdlib::binding(&key, &value);
+            slot.set(value);
+            Ok(())
         }else{
-            if let Some(parent) = &mut self.parent{
-                parent.borrow_mut().assign(key, value)
+            if let Some(parent) = &self.parent{
+                parent.borrow().assign(key, value)
             }else{
-                panic!("cannot assign variable <{}> because it does not exist", &key)
+                Err(RavenError::UndefinedVariable(key))
             }
         }
     }
@@ -63,60 +191,94 @@ impl Scope{
 
 #[derive(Clone, PartialEq)]
 pub enum FunctionTypes{
-    NormalFunction{code: Vec<Box<Type>>, scope: RefScope, parameters: Vec<String>},
+    /// `bound` holds the arguments a partial application already gave, `parameters` the ones still missing
+    NormalFunction{code: Rc<Vec<Box<Type>>>, scope: RefScope, parameters: Vec<String>, bound: Vec<Object>},
+    /// a function the vm compiled, called from either engine. `bound` and `parameters` work like they do for
+    /// `NormalFunction`
+    #[cfg(feature = "vm")]
+    Compiled{prototype: Rc<Prototype>, scope: RefScope, parameters: Vec<String>, bound: Vec<Object>},
     BuiltIn{Function: Func, parameters: u8},
 }
 impl Debug for FunctionTypes{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::NormalFunction { code, scope, parameters } => f.debug_struct("NormalFunction").field("code", code).field("parameters", parameters).finish(),
+            Self::NormalFunction { code, parameters, .. } => f.debug_struct("NormalFunction").field("code", code).field("parameters", parameters).finish(),
+            #[cfg(feature = "vm")]
+            Self::Compiled { prototype, parameters, .. } => f.debug_struct("Compiled").field("name", &prototype.name).field("parameters", parameters).finish(),
             Self::BuiltIn { Function, parameters } => f.debug_struct("BuiltIn").field("Function", Function).field("parameters", parameters).finish(),
         }
     }
 }
 
 impl FunctionTypes{
-    pub fn RunCode(code: &Vec<Box<Type>>, scope: RefScope) -> Option<Object>{
-        let mut result = None;
-        for line in code{
-            result = Interpreter::interpret(*line.clone(), scope.clone());
-        }
-        result
+    /// runs the body of a function. a call in tail position does not nest another `RunCode`, the callee's
+    /// body runs here after the caller's is done with
+    pub fn RunCode(code: &Rc<Vec<Box<Type>>>, scope: RefScope) -> RavenResult<Option<Object>>{
+        let _call = Call::enter()?;
+        depth::grow(|| {
+            let (mut code, mut scope) = (code.clone(), scope);
+            loop {
+                match Interpreter::body(&code, &scope) {
+                    Ok(value) | Err(Unwind::Return(value, _)) => return Ok(value),
+                    Err(Unwind::TailCall(callee, frame, _)) => (code, scope) = (callee, frame),
+                    Err(unwind) => return Err(unwind.intoError()),
+                }
+            }
+        })
     }
     pub fn isEnoughArgs(this: &Self, amount: u8) -> bool{
         match this{
-            FunctionTypes::NormalFunction { code: _, scope: _, parameters } => amount as usize == parameters.len(),
+            FunctionTypes::NormalFunction { parameters, .. } => amount as usize == parameters.len(),
+            #[cfg(feature = "vm")]
+            FunctionTypes::Compiled { parameters, .. } => amount as usize == parameters.len(),
             FunctionTypes::BuiltIn { Function: _, parameters } => amount >= *parameters,
         }
     }
 
-    pub fn call(this: &Self, _function: Object, evaluated_arguments: Vec<Rc<RefCell<Type>>>, scope: RefScope) -> Option<Object>{
+    /// the parameters still to be bound, builtins take any number past their minimum
+    pub fn parameters(&self) -> Option<&[String]>{
+        match self {
+            FunctionTypes::NormalFunction { parameters, .. } => Some(parameters),
+            #[cfg(feature = "vm")]
+            FunctionTypes::Compiled { parameters, .. } => Some(parameters),
+            FunctionTypes::BuiltIn { .. } => None,
+        }
+    }
+
+    pub fn call(this: &Self, _function: Object, evaluated_arguments: Vec<Rc<RefCell<Type>>>, scope: RefScope) -> RavenResult<Option<Object>>{
 
         match this {
-            FunctionTypes::NormalFunction { code, scope, parameters } => {
-                let new_scope = (**scope).borrow().clone();
-                let new_scope = Rc::new(RefCell::new(new_scope));
-
-                if evaluated_arguments.len() < parameters.len(){
-                    let new_code = code.clone();
-                    let mut new_parameters = parameters.clone();
-                    for (arg,param) in evaluated_arguments.into_iter().zip(parameters){
-                        new_scope.borrow_mut().declare(param.clone(), arg.clone());
-                        new_parameters.remove(0);
-                    }
-                    let function = FunctionTypes::NormalFunction { code: new_code, scope: new_scope, parameters: new_parameters };
-                    let functionObj = Type::Function(function);
-                    Some(functionObj.wrap())
+            _ if this.parameters().is_some_and(|parameters| evaluated_arguments.len() > parameters.len()) => {
+                Err(RavenError::ArityMismatch { expected: this.parameters().unwrap_or_default().len(), found: evaluated_arguments.len() })
+            },
+            FunctionTypes::NormalFunction { code, scope, parameters, bound } => {
+                // the parameters take the first slots of the frame, in order
+                let given = evaluated_arguments.len();
+                let arguments = bound.iter().cloned().chain(evaluated_arguments).collect();
+                if given < parameters.len(){
+                    let function = FunctionTypes::NormalFunction { code: code.clone(), scope: scope.clone(), parameters: parameters[given..].to_vec(), bound: arguments };
+                    Ok(Some(Type::Function(function).wrap()))
+                }else{
+                    FunctionTypes::RunCode(code, Scope::frame(scope.clone(), arguments))
+                }
+            },
+            #[cfg(feature = "vm")]
+            FunctionTypes::Compiled { prototype, scope, parameters, bound } => {
+                let given = evaluated_arguments.len();
+                let arguments = bound.iter().cloned().chain(evaluated_arguments).collect();
+                if given < parameters.len(){
+                    let function = FunctionTypes::Compiled { prototype: prototype.clone(), scope: scope.clone(), parameters: parameters[given..].to_vec(), bound: arguments };
+                    Ok(Some(Type::Function(function).wrap()))
                 }else{
-                    for (parameter,argument) in parameters.iter().zip(evaluated_arguments){
-                        new_scope.borrow_mut().declare(parameter.clone(), argument);
-                    }
-                    FunctionTypes::RunCode(&code, new_scope)
+                    let _call = Call::enter()?;
+                    depth::grow(|| RavenVM::reuse(prototype.clone(), Scope::frame(scope.clone(), arguments)))
                 }
             },
             FunctionTypes::BuiltIn { Function, parameters } => {
-                if evaluated_arguments.len() < *parameters as usize{panic!("Called function with {} parameters. Expected {} or more*", evaluated_arguments.len(), parameters)} 
-                return Function.0(scope.clone(), evaluated_arguments);
+                if evaluated_arguments.len() < *parameters as usize{
+                    return Err(RavenError::ArityMismatch { expected: *parameters as usize, found: evaluated_arguments.len() })
+                }
+                Function.0(scope.clone(), evaluated_arguments)
             },
         }
     }
@@ -125,125 +287,390 @@ impl FunctionTypes{
 
 
 pub struct Interpreter{
-    global: RefScope
+    /// the functions and objects added from rust, shared by the program and every module it imports
+    builtins: RefScope,
+    global: RefScope,
+    modules: Rc<RefCell<Modules>>,
+    /// how deep calls may nest before running fails with `RavenError::StackOverflow`
+    max_depth: usize,
+}
+/// frees what the scripts left behind. a `Value` kept past this point still holds its object, but the
+/// lists and maps the scripts can reach are emptied and the functions in it no longer see any variables
+impl Drop for Interpreter{
+    fn drop(&mut self){
+        let mut seen = HashSet::new();
+        for module in self.modules.borrow_mut().unload() {
+            Scope::releaseObject(&module, &mut seen);
+        }
+        Scope::release(&self.global, &mut seen);
+        Scope::release(&self.builtins, &mut seen);
+    }
+}
+impl Default for Interpreter{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 impl Interpreter{
+    /// an interpreter with just the builtins the syntax needs, `with_stdlib` adds the library
     pub fn new() -> Interpreter{
-        Interpreter { global: Scope::new() }
+        let builtins = Scope::new();
+        let global = Scope::with(builtins.clone());
+        let mut interpreter = Interpreter { builtins, global, modules: Rc::default(), max_depth: MAX_DEPTH };
+        stdlib::core(&mut interpreter);
+
+        // the builtins hold `__import__` itself, and the modules hold functions that see the builtins,
+        // strong references back would keep them all alive forever
+        let modules = Rc::downgrade(&interpreter.modules);
+        let builtins = Rc::downgrade(&interpreter.builtins);
+        interpreter.addFunction("__import__", 1, move |scope, args|{
+            let gone = || RavenError::Runtime("the interpreter is gone".to_string());
+            let (modules, builtins) = (modules.upgrade().ok_or_else(gone)?, builtins.upgrade().ok_or_else(gone)?);
+            let module = Modules::import(&modules, &builtins, &scope, &Self::String(args[0].clone())?)?;
+            match args.get(1) {
+                Some(name) => {
+                    let name = Self::String(name.clone())?;
+                    let export = Type::Member(module, &name).map_err(|_| RavenError::Runtime(format!("`{}` is not exported by {}", name, args[0].borrow().toString())))?;
+                    Ok(Some(export))
+                },
+                None => Ok(Some(module)),
+            }
+        });
+        interpreter
+    }
+
+    pub fn addFunction<T: 'static +  Fn(RefScope,Vec<Object>) -> RavenResult<Option<Object>>>(&mut self, name: &str, parameters: u8, f: T){
+        self.addBuiltIn(name, parameters, Func::new(Box::new(f)));
     }
 
-    pub fn addFunction<T: 'static +  Fn(RefScope,Vec<Object>) -> Option<Object>>(&mut self, name: &str, parameters: u8, f: T){
-        let obj = FunctionTypes::BuiltIn {Function: Func::new(Box::new(f)), parameters};
-        let obj = Type::Function(obj);
-        self.global.borrow_mut().declare(name.to_string(), Rc::new(RefCell::new(obj)));
+    pub(crate) fn addBuiltIn(&mut self, name: &str, parameters: u8, function: Func){
+        let obj = Type::Function(FunctionTypes::BuiltIn { Function: function, parameters });
+        self.builtins.borrow_mut().declare(name.to_string(), Rc::new(RefCell::new(obj)));
+    }
+
+    /// adds a rust closure as a builtin, reading its arguments with `FromRaven` and handing back its result
+    /// with `IntoRaven`. calls with too few or too many arguments, or ones of the wrong type, fail with an error
+    pub fn register<Arguments, F: Native<Arguments>>(&mut self, name: &str, function: F){
+        let (least, most) = F::arity();
+        self.addFunction(name, least as u8, move |_, arguments|{
+            if arguments.len() > most {
+                return Err(RavenError::ArityMismatch { expected: most, found: arguments.len() })
+            }
+            function.invoke(arguments)
+        });
     }
 
     pub fn addObject(&mut self, name: &str, value: Type){
-        self.global.borrow_mut().declare(name.to_string(), value.wrap());
+        self.builtins.borrow_mut().declare(name.to_string(), value.wrap());
     }
 
-    pub fn run(&mut self, code: String, debug: bool){
-        let node = ParseString(&code);
-        // println!("{:#?}", node);
+    /// runs `code` in the global scope, which keeps whatever it declares for the next run.
+    /// returns the value of the last line
+    pub fn run(&mut self, code: String, debug: bool) -> RavenResult<Option<Object>>{
+        let mut node = ParseString(&code)?;
+        Resolver::resolve(&mut node, &self.global)?;
 
         if debug {
-            print!("----------------\n");
-            for n in &node {
-                println!("{}\n", n.to_string(0, 0));
-            }
-            print!("----------------\n");
+            Self::debugPrint(&node);
         }
 
-        Self::interpretCode(node, self.global.clone());
+        self.modules.borrow_mut().vm = false;
+        let _limit = Limit::set(self.max_depth);
+        Self::interpretCode(&node, &self.global).map_err(Unwind::intoError)
+    }
+
+    /// every variable the code run so far declared in the global scope, sorted by name
+    pub fn globals(&self) -> Vec<(String, Object)>{
+        let mut globals = self.global.borrow().variables();
+        globals.sort_by(|a, b| a.0.cmp(&b.0));
+        globals
     }
 
-    fn interpretCode(code: Vec<Type>, scope: RefScope) -> Option<Object> {
+    /// runs `code` like `run` does and gives back the value of its last line, void if that is a statement.
+    /// with `addObject` for the inputs this makes raven an expression language: `price * qty`
+    pub fn eval(&mut self, code: &str) -> RavenResult<Value>{
+        self.run(code.to_string(), false).map(Value::from)
+    }
+
+    /// the global variable or builtin called `name`, if there is one
+    pub fn get_global(&self, name: &str) -> Option<Value>{
+        self.global.borrow().get(name).ok().map(|slot| Value::from(slot.get()))
+    }
+
+    /// calls the global function `name`, such as one a script declared with `fn`, and gives back its result
+    pub fn call(&mut self, name: &str, arguments: impl IntoArguments) -> RavenResult<Value>{
+        let function = self.global.borrow().get(name)?.get();
+        if !matches!(&*function.borrow(), Type::Function(_)) {
+            return Err(RavenError::NotCallable(name.to_string()))
+        }
+        let _limit = Limit::set(self.max_depth);
+        invoke(&function, arguments.intoArguments()?, self.global.clone()).map(Value::from)
+    }
+
+    /// how deep calls may nest, 10000 unless changed and 200 on wasm. calls in tail position, such as `f(n - 1)` as the
+    /// last line of `f` or in `return f(n - 1)`, reuse the caller's place and do not count
+    pub fn set_max_depth(&mut self, depth: usize){
+        self.max_depth = depth;
+    }
+
+    /// same as `run`, but compiles the file to bytecode and executes it on the `RavenVM`
+    #[cfg(feature = "vm")]
+    pub fn runVM(&mut self, code: String, debug: bool) -> RavenResult<Option<Object>>{
+        let mut node = ParseString(&code)?;
+        Resolver::resolve(&mut node, &self.global)?;
+        let program = Compiler::compile(&node)?;
+
+        if debug {
+            Self::debugPrint(&node);
+            print!("{}", program.chunk.disassemble());
+            println!("----------------");
+        }
+
+        self.modules.borrow_mut().vm = true;
+        let _limit = Limit::set(self.max_depth);
+        RavenVM::reuse(program, self.global.clone())
+    }
+
+    /// runs parsed code in `scope` on either engine, though without the `vm` feature there is only the tree walker
+    pub fn execute(mut code: Vec<Type>, scope: RefScope, vm: bool) -> RavenResult<Option<Object>>{
+        Resolver::resolve(&mut code, &scope)?;
+        #[cfg(feature = "vm")]
+        if vm {
+            return RavenVM::reuse(Compiler::compile(&code)?, scope)
+        }
+        #[cfg(not(feature = "vm"))]
+        let _ = vm;
+        Self::interpretCode(&code, &scope).map_err(Unwind::intoError)
+    }
+
+    fn debugPrint(node: &[Type]){
+        println!("----------------");
+        for n in node {
+            println!("{}\n", n.to_string(0, 0));
+        }
+        println!("----------------");
+    }
+
+    fn interpretCode(code: &[Type], scope: &RefScope) -> Flow {
         let mut result = None;
         for node in code{
-            result = Self::interpret(node, scope.clone());
+            result = Self::interpret(node, scope)?;
+        }
+
+        Ok(result)
+    }
+
+    /// evaluates `node` where it sits in the tree, loops and calls run the same nodes again rather than copies
+    fn interpret(node: &Type, scope: &RefScope) -> Flow{
+        Self::located(node, Self::evaluate(node, scope))
+    }
+
+    /// the errors in `flow` happened at `node`, unless they already know better
+    fn located(node: &Type, flow: Flow) -> Flow{
+        match node.span() {
+            Some(span) => flow.map_err(|unwind| match unwind {
+                Unwind::Error(error) => Unwind::Error(error.at(span)),
+                unwind => unwind
+            }),
+            None => flow
         }
+    }
+
+    /// runs the lines of a function body, the last of them in tail position
+    fn body<Line: std::borrow::Borrow<Type>>(code: &[Line], scope: &RefScope) -> Flow{
+        let Some((last, lines)) = code.split_last() else { return Ok(None) };
+        for line in lines {
+            Self::interpret(line.borrow(), scope)?;
+        }
+        Self::tail(last.borrow(), scope)
+    }
+
+    /// evaluates `node` as the last thing its function does. a call giving a raven function all the
+    /// arguments it still needs unwinds with `Unwind::TailCall` instead of running it
+    fn tail(node: &Type, scope: &RefScope) -> Flow{
+        let flow = match node {
+            Type::Call { function, arguments, span } => (|| {
+                let (name, functionObject, arguments) = Self::callee(function, arguments, scope)?;
+                let flow = match &*functionObject.borrow() {
+                    Type::Function(FunctionTypes::NormalFunction { code, scope, parameters, bound }) if arguments.len() == parameters.len() => {
+                        let arguments = bound.iter().cloned().chain(arguments).collect();
+                        Err(Unwind::TailCall(code.clone(), Scope::frame(scope.clone(), arguments), *span))
+                    },
+                    Type::Function(function) => Ok(FunctionTypes::call(function, functionObject.clone(), arguments, scope.clone())?),
+                    _ => Err(RavenError::NotCallable(name).into()),
+                };
+                flow
+            })(),
+            Type::Conditional { condition, then, otherwise, .. } => (|| {
+                if Self::Condition(condition, scope)? {
+                    Self::body(then, scope)
+                } else if let Some(otherwise) = otherwise {
+                    Self::body(otherwise, scope)
+                } else {
+                    Ok(None)
+                }
+            })(),
+            Type::Invocation { code } => Self::body(code, scope),
+            node => return Self::interpret(node, scope),
+        };
+        Self::located(node, flow)
+    }
 
-        result
+    /// evaluates the arguments of a call and then the function it calls, along with a name for errors
+    fn callee(function: &Type, arguments: &[Type], scope: &RefScope) -> Result<(String, Object, Vec<Object>), Unwind>{
+        let arguments = arguments.iter()
+        .map(|e| Interpreter::interpret(e, scope)?.ok_or_else(|| RavenError::VoidValue("an argument".to_string()).into()))
+        .collect::<Result<Vec<_>, Unwind>>()?;
+        let (name, functionObject) = match function {
+            Type::Symbol(name) => {
+                let functionObject = scope.borrow().get(name)?.get();
+                (name.clone(), functionObject)
+            },
+            Type::Local { name, depth, index } => {
+                let functionObject = scope.borrow().local(*depth, *index).ok_or_else(|| RavenError::UndefinedVariable(name.clone()))?;
+                (name.clone(), functionObject)
+            },
+            // `m.f(x)` and the like call whatever the expression evaluates to
+            callee => {
+                let functionObject = Self::interpret(callee, scope)?.ok_or_else(|| RavenError::VoidValue("a function".to_string()))?;
+                let name = functionObject.borrow().toString();
+                (name, functionObject)
+            },
+        };
+        Ok((name, functionObject, arguments))
     }
 
-    fn interpret(node: Type, scope: RefScope) -> Option<Object>{
+    fn evaluate(node: &Type, scope: &RefScope) -> Flow{
         match node{
-            Type::Call { function, arguments } => {
-                let name = Self::Symbol(*function);
-                let arguments = arguments.into_iter()
-                .map(|e| Interpreter::interpret(e, scope.clone()).unwrap_or_else(|| panic!("cannot use void as argument")))
-                .collect::<Vec<_>>();
-                // println!("args {:?}", arguments);
-                let functionObject = scope.borrow_mut().get(&name).0.clone();
+            Type::Call { function, arguments, .. } => {
+                let (name, functionObject, arguments) = Self::callee(function, arguments, scope)?;
                 if let Type::Function(function) = &*(*functionObject).borrow(){
-                    return FunctionTypes::call(function, functionObject.clone(), arguments, scope.clone())
+                    return Ok(FunctionTypes::call(function, functionObject.clone(), arguments, scope.clone())?)
+                }
+                Err(RavenError::NotCallable(name).into())
+            },
+            Type::VariableDeclaration { variable, value, .. } => {
+                let result = Self::interpret(value, scope)?.ok_or_else(|| RavenError::VoidValue("a variable's value".to_string()))?;
+                Self::declare(variable, result, scope)?;
+                Ok(None)
+            },
+            Type::Assignment { variable, value, .. } => {
+                let new_value = Interpreter::interpret(value, scope)?.ok_or_else(|| RavenError::VoidValue("a variable's value".to_string()))?;
+                match &**variable {
+                    Type::Local { name, depth, index } => {
+                        if !scope.borrow_mut().assignLocal(*depth, *index, new_value) {
+                            return Err(RavenError::UndefinedVariable(name.clone()).into())
+                        }
+                    },
+                    variable => scope.borrow_mut().assign(Self::Symbol(variable)?, new_value)?,
                 }
-                None
-            },
-            Type::VariableDeclaration { variable, value } => {
-                let result = Self::interpret(*value, scope.clone()).unwrap_or_else(|| panic!("attempted to assign void to a variable!"));
-                scope.borrow_mut()
-                .declare(Self::Symbol(*variable), result);
-                None
-            },
-            Type::Assignment { variable, value } => {
-                let new_value = Interpreter::interpret(*value, scope.clone()).unwrap();
-                scope.borrow_mut()
-                .assign(Self::Symbol(*variable), new_value);
-                None 
-            },
-            Type::CreateFunction { name, code, parameters } => {
-                let function  = FunctionTypes::NormalFunction { code, scope: scope.clone(), parameters };
+                Ok(None)
+            },
+            Type::CreateFunction { name, code, parameters, .. } => {
+                let function  = FunctionTypes::NormalFunction { code: code.clone(), scope: scope.clone(), parameters: parameters.clone(), bound: vec![] };
                 let function = Type::Function(function).wrap();
-                scope.borrow_mut()
-                .declare(Self::Symbol(*name), function.clone());
-                Some(function)
-            },
-            Type::Conditional { condition, then, otherwise } => {
-                if let Type::Bool(condition) = &*(*Interpreter::interpret(*condition, scope.clone()).unwrap()).borrow() {
-                    return if *condition {
-                        Interpreter::interpretCode(then, scope.clone())
-                    } else if let Some(otherwise) = otherwise {
-                        Interpreter::interpretCode(otherwise, scope.clone())
-                    } else {
-                        None
-                    }
+                Self::declare(name, function.clone(), scope)?;
+                Ok(Some(function))
+            },
+            Type::Conditional { condition, then, otherwise, .. } => {
+                if Self::Condition(condition, scope)? {
+                    Interpreter::interpretCode(then, scope)
+                } else if let Some(otherwise) = otherwise {
+                    Interpreter::interpretCode(otherwise, scope)
+                } else {
+                    Ok(None)
                 }
-                None
             },
-            Type::While { condition, code } => {
+            Type::While { condition, code, .. } => {
                 let mut result = None;
-                loop {
-                    if let Type::Bool(cond) = &*(*Interpreter::interpret(*condition.clone(), scope.clone()).unwrap()).borrow() {
-                        if !*cond { break }
-                        result = Interpreter::interpretCode(code.clone(), scope.clone());
-                    }
+                while Self::Condition(condition, scope)? {
+                    result = match Interpreter::interpretCode(code, scope) {
+                        Ok(value) => value,
+                        Err(Unwind::Break(_)) => { result = None; break },
+                        Err(Unwind::Continue(_)) => None,
+                        Err(unwind) => return Err(unwind),
+                    };
                 }
               
-                result
+                Ok(result)
+            },
+            Type::For { iterable, code, .. } => {
+                let iterable = Self::interpret(iterable, scope)?.ok_or_else(|| RavenError::VoidValue("a loop's iterable".to_string()))?;
+                let iterable = Type::Iterable(iterable)?;
+                let mut result = None;
+                let mut index = 0;
+                while let Some(item) = Type::Nth(&iterable, index)? {
+                    index += 1;
+                    // each iteration gets its own frame, with the loop variable in the first slot, so closures made
+                    // in the body keep their own one
+                    let iteration = Scope::frame(scope.clone(), vec![item]);
+                    result = match Interpreter::interpretCode(code, &iteration) {
+                        Ok(value) => value,
+                        Err(Unwind::Break(_)) => { result = None; break },
+                        Err(Unwind::Continue(_)) => None,
+                        Err(unwind) => return Err(unwind),
+                    };
+                }
+                Ok(result)
             },
             Type::Invocation { code } => {
-                Interpreter::interpretCode(code.clone(), scope.clone())
+                Interpreter::interpretCode(code, scope)
+            },
+            Type::Return { value, span } => {
+                let value = match value {
+                    // `return f(x)` is a tail call as well, one that is misplaced the way the `return` is
+                    Some(value) => match Self::tail(value, scope) {
+                        Err(Unwind::TailCall(code, frame, _)) => return Err(Unwind::TailCall(code, frame, *span)),
+                        flow => flow?,
+                    },
+                    None => None
+                };
+                Err(Unwind::Return(value, *span))
             },
+            Type::Break { span } => Err(Unwind::Break(*span)),
+            Type::Continue { span } => Err(Unwind::Continue(*span)),
             Type::Symbol(name) => {
-                let result = Some((*scope).borrow().get(&name).get().clone());
-                result
+                let result = Some((*scope).borrow().get(name)?.get());
+                Ok(result)
                 
             },
-            node @ _ => Some(Rc::new(RefCell::new(node)))
+            Type::Local { name, depth, index } => {
+                let result = scope.borrow().local(*depth, *index).ok_or_else(|| RavenError::UndefinedVariable(name.clone()))?;
+                Ok(Some(result))
+            },
+            // a literal, copied so the program can change its value without changing the tree
+            node => Ok(Some(node.clone().wrap()))
+        }
+    }
+
+    fn Condition(node: &Type, scope: &RefScope) -> Result<bool, Unwind>{
+        let condition = Interpreter::interpret(node, scope)?.ok_or_else(|| RavenError::VoidValue("a condition".to_string()))?;
+        let condition = &*condition.borrow();
+        match condition {
+            Type::Bool(condition) => Ok(*condition),
+            other => Err(RavenError::mismatch("branch on", &[other]).into())
         }
     }
     
-    fn Symbol(node: Type) -> String{
+    /// declares the variable `node` names, in its slot if the resolver gave it one
+    fn declare(node: &Type, value: Object, scope: &RefScope) -> RavenResult<()>{
+        match node {
+            Type::Local { index, .. } => scope.borrow_mut().declareLocal(*index, value),
+            node => scope.borrow_mut().declare(Self::Symbol(node)?, value),
+        }
+        Ok(())
+    }
+
+    fn Symbol(node: &Type) -> RavenResult<String>{
         if let Type::Symbol(symbol) = node{
-            return symbol
+            return Ok(symbol.clone())
         }
-        panic!("node <{:?}> was not a symbol!", node)
+        Err(RavenError::NotCallable(node.toString()))
     }
-    pub fn String(node: Object) -> String{
+    pub fn String(node: Object) -> RavenResult<String>{
         if let Type::String(str) = &*(*node).borrow(){
-            return str.to_owned()
+            return Ok(str.to_owned())
         }
-        panic!("node <{:?}> was not a string!", node)
+        Err(RavenError::mismatch("use as a string", &[&node.borrow()]))
     }
 }
\ No newline at end of file
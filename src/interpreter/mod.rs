@@ -1 +1,8 @@
-pub mod interpreter;
\ No newline at end of file
+pub mod interpreter;
+pub mod error;
+pub mod depth;
+pub mod module;
+pub mod resolver;
+pub mod stdlib;
+pub mod convert;
+pub mod value;
@@ -0,0 +1,67 @@
+use std::{cell::RefCell, collections::{BTreeMap, HashMap}, fs::read_to_string, path::{Path, PathBuf}, rc::Rc};
+
+use crate::parser::parser::{ParseString, Type};
+use super::error::{RavenError, RavenResult};
+use super::interpreter::{Interpreter, Object, RefScope, Scope};
+
+/// the modules an interpreter has loaded, so each file runs once however often it is imported
+#[derive(Debug, Default)]
+pub struct Modules{
+    loaded: HashMap<PathBuf, Object>,
+    /// the files being loaded right now, each imported by the one before it
+    loading: Vec<(PathBuf, String)>,
+    /// whether modules run on the bytecode vm, like the program importing them
+    pub vm: bool,
+}
+
+impl Modules{
+    /// forgets every module, giving back what they were
+    pub(crate) fn unload(&mut self) -> Vec<Object>{
+        self.loaded.drain().map(|(_, module)| module).collect()
+    }
+
+    /// the module at `path` as a map of the variables it declares at the top level. `path` is relative
+    /// to the `__file__` that `scope` sees, or to the working directory when there is none
+    pub fn import(modules: &Rc<RefCell<Modules>>, builtins: &RefScope, scope: &RefScope, path: &str) -> RavenResult<Object>{
+        let importer = scope.borrow().get("__file__").map(|slot| slot.get());
+        let path = match importer {
+            Ok(file) => Path::new(&Interpreter::String(file)?).parent().unwrap_or(Path::new("")).join(path),
+            Err(_) => PathBuf::from(path),
+        };
+        let name = path.display().to_string();
+        let key = path.canonicalize().map_err(|error| RavenError::Io(format!("cannot read {}: {}", name, error)))?;
+
+        {
+            let mut modules = modules.borrow_mut();
+            if let Some(module) = modules.loaded.get(&key) {
+                return Ok(module.clone())
+            }
+            if let Some(at) = modules.loading.iter().position(|(loading, _)| *loading == key) {
+                let cycle = modules.loading[at..].iter().map(|(_, name)| name.as_str()).chain([name.as_str()]).collect::<Vec<_>>();
+                return Err(RavenError::Runtime(format!("circular import: {}", cycle.join(" -> "))))
+            }
+            modules.loading.push((key.clone(), name.clone()));
+        }
+        // the module may import others, so `modules` cannot stay borrowed while it runs
+        let vm = modules.borrow().vm;
+        let module = Self::load(&name, builtins, vm);
+        modules.borrow_mut().loading.pop();
+        let module = module?;
+        modules.borrow_mut().loaded.insert(key, module.clone());
+        Ok(module)
+    }
+
+    /// runs the file in a scope of its own that sees nothing but the builtins
+    fn load(file: &str, builtins: &RefScope, vm: bool) -> RavenResult<Object>{
+        let source = read_to_string(file).map_err(|error| RavenError::Io(format!("cannot read {}: {}", file, error)))?;
+        let scope = Scope::with(builtins.clone());
+        scope.borrow_mut().declare("__file__".to_string(), Type::String(file.to_string()).wrap());
+
+        let inside = |error| RavenError::Import { file: file.to_string(), source: source.clone(), error: Box::new(error) };
+        let code = ParseString(&source).map_err(inside)?;
+        Interpreter::execute(code, scope.clone(), vm).map_err(inside)?;
+
+        let exports = scope.borrow().variables().into_iter().filter(|(name, _)| name != "__file__");
+        Ok(Type::Map(exports.collect::<BTreeMap<_, _>>()).wrap())
+    }
+}
@@ -0,0 +1,186 @@
+use std::{collections::HashSet, rc::Rc};
+
+use crate::parser::{parser::Type, span::Span};
+use super::error::{RavenError, RavenResult};
+use super::interpreter::RefScope;
+
+/// the pass between parsing and running. it fails on variables that are never declared before any of the
+/// code runs, and turns the ones declared inside a function or a loop body into `Type::Local`, so the
+/// interpreter finds them by position instead of by name.
+///
+/// the top level stays looked up by name: it is the scope the repl, `Interpreter::addObject` and imports
+/// keep adding to, and a function may call one declared further down the file
+pub struct Resolver<'a>{
+    /// what earlier runs, the builtins and the module itself declared
+    scope: &'a RefScope,
+    /// every name the top level of the code declares, wherever it does so
+    globals: HashSet<String>,
+    /// the function or loop bodies around the code being resolved, innermost last
+    frames: Vec<Frame>,
+    span: Span,
+}
+
+/// the variables of a function or loop body. a variable's index is its position in `variables`
+struct Frame{
+    /// the parameters or loop variable, then everything the body declares, wherever it does so
+    variables: Vec<String>,
+    /// whether the resolver got past the declaration of each variable yet
+    declared: Vec<bool>,
+}
+
+impl<'a> Resolver<'a>{
+    /// resolves `code` for running in `scope`
+    pub fn resolve(code: &mut [Type], scope: &'a RefScope) -> RavenResult<()>{
+        let mut globals = vec![];
+        for line in code.iter() {
+            Self::declarations(line, &mut globals);
+        }
+        let globals = globals.into_iter().collect();
+        let mut resolver = Resolver { scope, globals, frames: vec![], span: Span::default() };
+        resolver.block(code.iter_mut())
+    }
+
+    /// the names `node` declares in the scope it runs in, in order and each once. blocks other than function
+    /// and loop bodies share that scope, so `if c { let x = 1 }` declares `x` too
+    fn declarations(node: &Type, names: &mut Vec<String>){
+        let mut nested = |code: &[Type]| code.iter().for_each(|line| Self::declarations(line, names));
+        let declare = |names: &mut Vec<String>, name: &String| if !names.contains(name) { names.push(name.clone()) };
+        match node {
+            Type::VariableDeclaration { variable, value, .. } => {
+                nested(std::slice::from_ref(&**value));
+                if let Type::Symbol(name) = &**variable {
+                    declare(names, name);
+                }
+            },
+            Type::CreateFunction { name, .. } => {
+                if let Type::Symbol(name) = &**name {
+                    declare(names, name);
+                }
+            },
+            Type::Call { function, arguments, .. } => {
+                nested(std::slice::from_ref(&**function));
+                nested(arguments);
+            },
+            Type::Assignment { value, .. } => nested(std::slice::from_ref(&**value)),
+            Type::Conditional { condition, then, otherwise, .. } => {
+                nested(std::slice::from_ref(&**condition));
+                nested(then);
+                nested(otherwise.as_deref().unwrap_or_default());
+            },
+            Type::While { condition, code, .. } => {
+                nested(std::slice::from_ref(&**condition));
+                nested(code);
+            },
+            Type::For { iterable, .. } => nested(std::slice::from_ref(&**iterable)),
+            Type::Invocation { code } => nested(code),
+            Type::Return { value: Some(value), .. } => nested(std::slice::from_ref(&**value)),
+            _ => {}
+        }
+    }
+
+    fn block<'b>(&mut self, code: impl Iterator<Item = &'b mut Type>) -> RavenResult<()>{
+        for line in code {
+            self.expression(line)?;
+        }
+        Ok(())
+    }
+
+    /// resolves `code` as the body of a function or loop whose frame starts out with `variables`. what the
+    /// body declares gets its slot up front, so the functions in it can use a variable declared after them
+    fn frame<Line: std::borrow::BorrowMut<Type>>(&mut self, mut variables: Vec<String>, code: &mut [Line]) -> RavenResult<()>{
+        let mut declared = vec![true; variables.len()];
+        let mut declarations = vec![];
+        code.iter().for_each(|line| Self::declarations(line.borrow(), &mut declarations));
+        for name in declarations {
+            if !variables.contains(&name) {
+                variables.push(name);
+                declared.push(false);
+            }
+        }
+        self.frames.push(Frame { variables, declared });
+        let result = self.block(code.iter_mut().map(|line| line.borrow_mut()));
+        self.frames.pop();
+        result
+    }
+
+    fn expression(&mut self, node: &mut Type) -> RavenResult<()>{
+        let outer = self.span;
+        self.span = node.span().unwrap_or(outer);
+        let result = self.node(node);
+        self.span = outer;
+        result
+    }
+
+    fn node(&mut self, node: &mut Type) -> RavenResult<()>{
+        match node {
+            Type::Symbol(_) => self.variable(node)?,
+            Type::Call { function, arguments, .. } => {
+                self.block(arguments.iter_mut())?;
+                self.expression(function)?;
+            },
+            Type::VariableDeclaration { variable, value, .. } => {
+                // `let x = x + 1` still sees the `x` from before
+                self.expression(value)?;
+                self.declare(variable);
+            },
+            Type::Assignment { variable, value, .. } => {
+                self.expression(value)?;
+                self.variable(variable)?;
+            },
+            Type::CreateFunction { name, code, parameters, .. } => {
+                // declared before the body, so the function can call itself
+                self.declare(name);
+                self.frame(parameters.clone(), Rc::make_mut(code).as_mut_slice())?;
+            },
+            Type::Conditional { condition, then, otherwise, .. } => {
+                self.expression(condition)?;
+                self.block(then.iter_mut())?;
+                self.block(otherwise.iter_mut().flatten())?;
+            },
+            Type::While { condition, code, .. } => {
+                self.expression(condition)?;
+                self.block(code.iter_mut())?;
+            },
+            Type::For { variable, iterable, code, .. } => {
+                self.expression(iterable)?;
+                self.frame(vec![variable.clone()], code)?;
+            },
+            Type::Invocation { code } => self.block(code.iter_mut())?,
+            Type::Return { value: Some(value), .. } => self.expression(value)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// gives the variable `node` declares its slot in the innermost frame. the top level keeps its variables by name
+    fn declare(&mut self, node: &mut Type){
+        let (Some(frame), Type::Symbol(name)) = (self.frames.last_mut(), &*node) else { return };
+        let index = match frame.variables.iter().position(|variable| variable == name) {
+            Some(index) => index,
+            None => {
+                frame.variables.push(name.clone());
+                frame.declared.push(false);
+                frame.variables.len() - 1
+            }
+        };
+        frame.declared[index] = true;
+        *node = Type::Local { name: name.clone(), depth: 0, index };
+    }
+
+    /// resolves the variable `node` uses to the closest frame declaring it, or checks that the top level has
+    /// it. in its own frame a variable is only there once its declaration ran, so `let x = x + 1` reads the
+    /// `x` from outside, while a function or loop inside the frame may run after any of it
+    fn variable(&mut self, node: &mut Type) -> RavenResult<()>{
+        let Type::Symbol(name) = &*node else { return Ok(()) };
+        let local = self.frames.iter().rev().enumerate().find_map(|(depth, frame)| {
+            let index = frame.variables.iter().position(|variable| variable == name)?;
+            (depth > 0 || frame.declared[index]).then_some((depth, index))
+        });
+        match local {
+            Some((depth, index)) => *node = Type::Local { name: name.clone(), depth, index },
+            None if self.globals.contains(name) || self.scope.borrow().get(name).is_ok() => {},
+            None => return Err(RavenError::UndefinedVariable(name.clone()).at(self.span)),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,328 @@
+//! the builtins that come with the interpreter.
+//!
+//! `Interpreter::new` only installs what the syntax lowers to: the operators, list and map
+//! literals, indexing, member access, `import`, `true` and `false`. `Interpreter::with_stdlib`
+//! adds the library on top of that:
+//!
+//! - io: `print(values...)`
+//! - types: `type_of(x)` names the type of `x`, `str(x)` turns it into a string and
+//!   `number(x)` parses one back into an integer or a float
+//! - math: `floor`, `ceil`, `round`, `abs`, `sqrt`, `sin`, `cos`, `tan`, `min(values...)` and
+//!   `max(values...)`, which also take a single list, `random()` for a float between 0 and 1 and
+//!   `random(start, end)` for an integer from `start` up to but not including `end`, and `pi`
+//! - strings: `split(text, separator)`, which splits on whitespace without a separator,
+//!   `join(list, separator)`, `trim`, `upper`, `lower`, `replace(text, from, to)` and
+//!   `contains(text, part)`, which also looks for an element in a list
+//! - collections: `len`, `push(list, values...)`, `pop`, `keys`, `values`, `has(map, key)` and
+//!   `remove(map, key)`
+//! - assertions: `assert(condition, message...)` and `assert_eq(x, y)`, which fail with a
+//!   runtime error that says what did not hold
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+
+use rand::Rng;
+
+use crate::parser::{func::equal, parser::{Func, Type}};
+use super::error::{RavenError, RavenResult};
+use super::interpreter::{FunctionTypes, Interpreter, Object};
+
+impl Interpreter{
+    /// an interpreter with the whole standard library installed, see the module documentation
+    pub fn with_stdlib() -> Interpreter{
+        let mut interpreter = Interpreter::new();
+        io(&mut interpreter);
+        types(&mut interpreter);
+        math(&mut interpreter);
+        strings(&mut interpreter);
+        collections(&mut interpreter);
+        assertions(&mut interpreter);
+        interpreter
+    }
+}
+
+/// a numeric argument as a float, whether it is one or an integer
+fn float(object: &Object, operation: &str) -> RavenResult<f64>{
+    match &*object.borrow() {
+        Type::Number(number) => Ok(*number as f64),
+        Type::Integer(number) => Ok(*number as f64),
+        other => Err(RavenError::mismatch(operation, &[other])),
+    }
+}
+
+fn integer(object: &Object, operation: &str) -> RavenResult<i64>{
+    match &*object.borrow() {
+        Type::Integer(number) => Ok(*number),
+        other => Err(RavenError::mismatch(operation, &[other])),
+    }
+}
+
+/// `floor`, `ceil` and `round` keep integers as they are and turn floats into integers
+fn rounding(i: &mut Interpreter, name: &'static str, round: fn(f64) -> f64){
+    i.addFunction(name, 1, move |_, args|{
+        if let Type::Integer(_) = &*args[0].borrow() {
+            return Ok(Some(args[0].clone()))
+        }
+        let number = round(float(&args[0], name)?);
+        if !number.is_finite() || number.abs() >= i64::MAX as f64 {
+            return Err(RavenError::Runtime(format!("cannot {} {} to an integer", name, number)))
+        }
+        Ok(Some(Type::Integer(number as i64).wrap()))
+    });
+}
+
+/// `min` and `max`: the value that `better` prefers over every other one
+fn extreme(i: &mut Interpreter, name: &'static str, better: fn(Object, Object) -> RavenResult<Object>){
+    i.addFunction(name, 1, move |_, args|{
+        let values = match &*args[0].borrow() {
+            Type::List(items) if args.len() == 1 => items.clone(),
+            _ => args.clone(),
+        };
+        let mut values = values.into_iter();
+        let first = values.next().ok_or_else(|| RavenError::Runtime(format!("cannot take the {} of an empty list", name)))?;
+        values.try_fold(first, |best, value| {
+            let wins = matches!(*better(value.clone(), best.clone())?.borrow(), Type::Bool(true));
+            Ok(if wins { value } else { best })
+        }).map(Some)
+    });
+}
+
+thread_local!{
+    /// the builtins `operator` made, shared by the interpreters on a thread so the vm can tell them apart
+    /// from functions a script or the host declared under the same name
+    static OPERATORS: RefCell<HashMap<&'static str, Func>> = RefCell::new(HashMap::new());
+    /// set once something else goes under one of their names, in any scope of any interpreter on the thread
+    static REPLACED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// adds a builtin the syntax lowers an operator to. the syntax always passes exactly `arity` operands, but
+/// a script or the host can call it by name with any number
+fn operator(i: &mut Interpreter, name: &'static str, arity: usize, f: fn(&[Object]) -> RavenResult<Object>){
+    let function = OPERATORS.with(|operators| operators.borrow_mut().entry(name).or_insert_with(|| Func::new(Box::new(move |_, args|{
+        if args.len() > arity {
+            return Err(RavenError::ArityMismatch { expected: arity, found: args.len() })
+        }
+        f(&args).map(Some)
+    }))).clone());
+    i.addBuiltIn(name, arity as u8, function);
+}
+
+/// whether `function` is still the builtin `Interpreter::new` put under `name`
+pub(crate) fn isOperator(name: &str, function: &Type) -> bool{
+    match function {
+        Type::Function(FunctionTypes::BuiltIn { Function, .. }) => OPERATORS.with(|operators| operators.borrow().get(name) == Some(Function)),
+        _ => false
+    }
+}
+
+/// notes `value` being declared or assigned under `name`, see `replaced`
+pub(crate) fn binding(name: &str, value: &Object){
+    if name.starts_with("__") && !REPLACED.with(Cell::get) && OPERATORS.with(|operators| operators.borrow().contains_key(name))
+        && !isOperator(name, &value.borrow()) {
+        REPLACED.with(|replaced| replaced.set(true));
+    }
+}
+
+/// whether an operator's name might hold something besides its builtin. until then the vm works out
+/// numbers, integers and bools without looking the name up
+#[cfg(feature = "vm")]
+pub(crate) fn replaced() -> bool{
+    REPLACED.with(Cell::get)
+}
+
+/// what the syntax lowers to, every interpreter needs these whatever else it offers
+pub(crate) fn core(i: &mut Interpreter){
+    operator(i, "__add__", 2, |args| Type::Add(args[0].clone(), args[1].clone()));
+    operator(i, "__sub__", 2, |args| Type::Subtract(args[0].clone(), args[1].clone()));
+    operator(i, "__mul__", 2, |args| Type::Multiply(args[0].clone(), args[1].clone()));
+    operator(i, "__div__", 2, |args| Type::Divide(args[0].clone(), args[1].clone()));
+    operator(i, "__pow__", 2, |args| Type::Power(args[0].clone(), args[1].clone()));
+    operator(i, "__mod__", 2, |args| Type::Modulo(args[0].clone(), args[1].clone()));
+    operator(i, "__eq__", 2, |args| Type::Equals(args[0].clone(), args[1].clone()));
+    operator(i, "__ne__", 2, |args| Type::NotEquals(args[0].clone(), args[1].clone()));
+    operator(i, "__le__", 2, |args| Type::LessThanOrEquals(args[0].clone(), args[1].clone()));
+    operator(i, "__ge__", 2, |args| Type::GreaterThanOrEquals(args[0].clone(), args[1].clone()));
+    operator(i, "__lt__", 2, |args| Type::LessThan(args[0].clone(), args[1].clone()));
+    operator(i, "__gt__", 2, |args| Type::GreaterThan(args[0].clone(), args[1].clone()));
+    operator(i, "__and__", 2, |args| Type::And(args[0].clone(), args[1].clone()));
+    operator(i, "__or__", 2, |args| Type::Or(args[0].clone(), args[1].clone()));
+    operator(i, "__not__", 1, |args| Type::Not(args[0].clone()));
+    operator(i, "__range__", 2, |args| Type::MakeRange(args[0].clone(), args[1].clone()));
+    i.addFunction("__list__", 0, |_, args|{
+        Ok(Some(Type::List(args).wrap()))
+    });
+    operator(i, "__index__", 2, |args| Type::Index(args[0].clone(), args[1].clone()));
+    operator(i, "__member__", 2, |args| Type::Member(args[0].clone(), &Interpreter::String(args[1].clone())?));
+    i.addFunction("__setindex__", 3, |_, args|{
+        if args.len() > 3 {
+            return Err(RavenError::ArityMismatch { expected: 3, found: args.len() })
+        }
+        Type::SetIndex(args[0].clone(), args[1].clone(), args[2].clone())?;
+        Ok(None)
+    });
+    i.addFunction("__map__", 0, |_, args|{
+        if args.len() % 2 != 0 {
+            return Err(RavenError::Runtime("map literal is missing a value".to_string()))
+        }
+        let mut entries = BTreeMap::new();
+        for pair in args.chunks(2) {
+            entries.insert(Interpreter::String(pair[0].clone())?, pair[1].clone());
+        }
+        Ok(Some(Type::Map(entries).wrap()))
+    });
+
+    i.addObject("true", Type::Bool(true));
+    i.addObject("false", Type::Bool(false));
+}
+
+fn io(i: &mut Interpreter){
+    i.addFunction("print", 1, |_, args|{
+        let text = args.into_iter().map(|e| e.borrow().toString()).collect::<Vec<_>>().join(" ");
+        println!("{}", text);
+        Ok(None)
+    });
+}
+
+fn types(i: &mut Interpreter){
+    i.register("type_of", |value: Object| value.borrow().typeName());
+    i.register("str", |value: Object| value.borrow().toString());
+    i.addFunction("number", 1, |_, args|{
+        let text = args[0].borrow().toString();
+        if let Ok(integer) = text.parse::<i64>() {
+            return Ok(Some(Type::Integer(integer).wrap()))
+        }
+        match text.parse::<f32>() {
+            Ok(number) => Ok(Some(Type::Number(number).into())),
+            Err(_) => Err(RavenError::Runtime(format!("cannot convert `{}` to a number", text))),
+        }
+    });
+}
+
+fn math(i: &mut Interpreter){
+    rounding(i, "floor", f64::floor);
+    rounding(i, "ceil", f64::ceil);
+    rounding(i, "round", f64::round);
+    i.register("sqrt", f64::sqrt);
+    i.register("sin", f64::sin);
+    i.register("cos", f64::cos);
+    i.register("tan", f64::tan);
+    i.addFunction("abs", 1, |_, args|{
+        match &*args[0].borrow() {
+            Type::Integer(number) => number.checked_abs().map(|number| Some(Type::Integer(number).wrap()))
+                .ok_or_else(|| RavenError::Runtime("integer overflow".to_string())),
+            Type::Number(number) => Ok(Some(Type::Number(number.abs()).wrap())),
+            other => Err(RavenError::mismatch("take the absolute value of", &[other])),
+        }
+    });
+    extreme(i, "min", Type::LessThan);
+    extreme(i, "max", Type::GreaterThan);
+    i.addFunction("random", 0, |_, args|{
+        match args.as_slice() {
+            [] => Ok(Some(Type::Number(rand::random::<f32>()).wrap())),
+            [start, end] => {
+                let (start, end) = (integer(start, "pick a random number between")?, integer(end, "pick a random number between")?);
+                if start >= end {
+                    return Err(RavenError::Runtime(format!("cannot pick a random number from the empty range {}..{}", start, end)))
+                }
+                Ok(Some(Type::Integer(rand::thread_rng().gen_range(start..end)).wrap()))
+            },
+            _ => Err(RavenError::ArityMismatch { expected: 2, found: args.len() }),
+        }
+    });
+    i.addObject("pi", Type::Number(std::f32::consts::PI));
+}
+
+fn strings(i: &mut Interpreter){
+    i.register("split", |text: String, separator: Option<String>| -> Vec<String> {
+        match separator.as_deref() {
+            None => text.split_whitespace().map(str::to_string).collect(),
+            Some("") => text.chars().map(String::from).collect(),
+            Some(separator) => text.split(separator).map(str::to_string).collect(),
+        }
+    });
+    i.register("join", |items: Vec<Object>, separator: Option<String>| {
+        items.iter().map(|item| item.borrow().toString()).collect::<Vec<_>>().join(&separator.unwrap_or_default())
+    });
+    i.register("trim", |text: String| text.trim().to_string());
+    i.register("upper", |text: String| text.to_uppercase());
+    i.register("lower", |text: String| text.to_lowercase());
+    i.register("replace", |text: String, from: String, to: String| text.replace(&from, &to));
+    i.addFunction("contains", 2, |_, args|{
+        let found = match &*args[0].borrow() {
+            Type::String(text) => text.contains(&Interpreter::String(args[1].clone())?),
+            Type::List(items) => items.iter().any(|item| equal(&item.borrow(), &args[1].borrow())),
+            other => return Err(RavenError::mismatch("look for something in", &[other])),
+        };
+        Ok(Some(Type::Bool(found).wrap()))
+    });
+}
+
+fn collections(i: &mut Interpreter){
+    i.addFunction("len", 1, |_, args|{
+        match &*args[0].borrow() {
+            Type::List(items) => Ok(Some(Type::Integer(items.len() as i64).wrap())),
+            Type::String(text) => Ok(Some(Type::Integer(text.chars().count() as i64).wrap())),
+            Type::Map(entries) => Ok(Some(Type::Integer(entries.len() as i64).wrap())),
+            Type::Range(start, end) => Ok(Some(Type::Integer(end.saturating_sub(*start).max(0)).wrap())),
+            other => Err(RavenError::mismatch("take the length of", &[other])),
+        }
+    });
+    i.addFunction("push", 2, |_, args|{
+        match &mut *args[0].borrow_mut() {
+            Type::List(items) => {
+                items.extend(args[1..].iter().cloned());
+                Ok(None)
+            },
+            other => Err(RavenError::mismatch("push onto", &[other])),
+        }
+    });
+    i.addFunction("pop", 1, |_, args|{
+        match &mut *args[0].borrow_mut() {
+            Type::List(items) => items.pop().map(Some).ok_or_else(|| RavenError::Runtime("cannot pop from an empty list".to_string())),
+            other => Err(RavenError::mismatch("pop from", &[other])),
+        }
+    });
+    i.addFunction("keys", 1, |_, args|{
+        match &*args[0].borrow() {
+            Type::Map(entries) => Ok(Some(Type::List(entries.keys().map(|key| Type::String(key.clone()).wrap()).collect()).wrap())),
+            other => Err(RavenError::mismatch("take the keys of", &[other])),
+        }
+    });
+    i.addFunction("values", 1, |_, args|{
+        match &*args[0].borrow() {
+            Type::Map(entries) => Ok(Some(Type::List(entries.values().cloned().collect()).wrap())),
+            other => Err(RavenError::mismatch("take the values of", &[other])),
+        }
+    });
+    i.addFunction("has", 2, |_, args|{
+        let key = Interpreter::String(args[1].clone())?;
+        match &*args[0].borrow() {
+            Type::Map(entries) => Ok(Some(Type::Bool(entries.contains_key(&key)).wrap())),
+            other => Err(RavenError::mismatch("look up a key in", &[other])),
+        }
+    });
+    i.addFunction("remove", 2, |_, args|{
+        let key = Interpreter::String(args[1].clone())?;
+        match &mut *args[0].borrow_mut() {
+            Type::Map(entries) => entries.remove(&key).map(Some).ok_or_else(|| RavenError::missing(&key)),
+            other => Err(RavenError::mismatch("remove a key from", &[other])),
+        }
+    });
+}
+
+fn assertions(i: &mut Interpreter){
+    i.addFunction("assert", 1, |_, args|{
+        let message = args[1..].iter().map(|e| e.borrow().toString()).collect::<Vec<_>>().join(" ");
+        match &*args[0].borrow() {
+            Type::Bool(true) => Ok(None),
+            Type::Bool(false) if message.is_empty() => Err(RavenError::Runtime("assertion failed".to_string())),
+            Type::Bool(false) => Err(RavenError::Runtime(format!("assertion failed: {}", message))),
+            other => Err(RavenError::mismatch("assert", &[other])),
+        }
+    });
+    i.addFunction("assert_eq", 2, |_, args|{
+        if equal(&args[0].borrow(), &args[1].borrow()) {
+            return Ok(None)
+        }
+        Err(RavenError::Runtime(format!("assertion failed: {} != {}", args[0].borrow().quoted(), args[1].borrow().quoted())))
+    });
+}
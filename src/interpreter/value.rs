@@ -0,0 +1,87 @@
+use std::fmt::Display;
+
+use super::convert::{invoke, FromRaven, IntoArguments, IntoRaven};
+use super::error::{RavenError, RavenResult};
+use super::interpreter::{Object, Scope};
+
+/// a raven value held on the rust side, as `Interpreter::call` and `Interpreter::get_global` hand them
+/// out. it is void when it comes from something that gives no value, like a statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct Value(Option<Object>);
+
+impl Value{
+    pub fn new<T: IntoRaven>(value: T) -> RavenResult<Value>{
+        Ok(Value(value.intoRaven()?))
+    }
+
+    pub fn void() -> Value{
+        Value(None)
+    }
+
+    pub fn isVoid(&self) -> bool{
+        self.0.is_none()
+    }
+
+    /// the value as a rust one, void only reads as an `Option`
+    pub fn get<T: FromRaven>(&self) -> RavenResult<T>{
+        match &self.0 {
+            Some(object) => T::fromRaven(object),
+            None => T::missing().ok_or_else(|| RavenError::VoidValue("a value".to_string())),
+        }
+    }
+
+    /// the object itself, changing it changes it for the script too
+    pub fn object(&self) -> Option<Object>{
+        self.0.clone()
+    }
+
+    pub fn typeName(&self) -> &'static str{
+        match &self.0 {
+            Some(object) => object.borrow().typeName(),
+            None => "void",
+        }
+    }
+
+    /// calls the value, if it is a function
+    pub fn call(&self, arguments: impl IntoArguments) -> RavenResult<Value>{
+        let function = self.0.as_ref().ok_or_else(|| RavenError::NotCallable("void".to_string()))?;
+        invoke(function, arguments.intoArguments()?, Scope::new()).map(Value)
+    }
+}
+
+impl From<Object> for Value{
+    fn from(object: Object) -> Self{
+        Value(Some(object))
+    }
+}
+
+impl From<Option<Object>> for Value{
+    fn from(object: Option<Object>) -> Self{
+        Value(object)
+    }
+}
+
+impl Display for Value{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Some(object) => write!(f, "{}", object.borrow().toString()),
+            None => write!(f, "void"),
+        }
+    }
+}
+
+impl FromRaven for Value{
+    fn fromRaven(object: &Object) -> RavenResult<Self>{
+        Ok(Value(Some(object.clone())))
+    }
+
+    fn missing() -> Option<Self>{
+        Some(Value(None))
+    }
+}
+
+impl IntoRaven for Value{
+    fn intoRaven(self) -> RavenResult<Option<Object>>{
+        Ok(self.0)
+    }
+}
@@ -0,0 +1,34 @@
+//! raven, a small scripting language to embed in rust programs:
+//!
+//! ```
+//! use raven::Interpreter;
+//!
+//! let mut raven = Interpreter::with_stdlib();
+//! raven.register("double", |x: i64| x * 2);
+//! let total = raven.eval("let xs = [1, 2, 3]\ndouble(xs[2])").unwrap();
+//! assert_eq!(total.get::<i64>().unwrap(), 6);
+//! ```
+//!
+//! the `raven` binary is built on top of this. the default features `color`, `vm` and `repl` can be turned
+//! off for plain text errors, no bytecode engine and no terminal session
+#![allow(non_snake_case, clippy::module_inception)]
+
+pub mod cli;
+pub mod color;
+pub mod parser;
+// the terminal is not there on the web
+#[cfg(all(feature = "repl", not(target_arch = "wasm32")))]
+pub mod repl;
+// most tests hold the two engines against each other
+#[cfg(test)]
+mod tests;
+#[cfg(feature = "vm")]
+pub mod vm;
+pub mod interpreter;
+pub mod wasm;
+
+pub use interpreter::convert::{Callback, FromRaven, IntoArguments, IntoRaven};
+pub use interpreter::error::{RavenError, RavenResult};
+pub use interpreter::interpreter::{Interpreter, Object, Scope};
+pub use interpreter::value::Value;
+pub use parser::parser::{ParseString, Type};
@@ -1,109 +1,81 @@
 #![allow(non_snake_case)]
-use std::{fs::read_to_string, cell::RefCell, rc::Rc};
-
-use interpreter::interpreter::Interpreter;
-use parser::parser::{ParseFile, Type};
-
-pub mod parser;
-mod tests;
-pub mod vm;
-pub mod interpreter;
-
-// #[cfg(target_arch = "wasm32")]
-pub mod wasm;
-
-
-// #[macro_use]
-// extern crate lazy_static;
-
+use std::fs::read_to_string;
 
+use raven::color::Colorize;
+use raven::cli::{self, Command, Options};
+use raven::{parser, Interpreter, ParseString, RavenError, RavenResult, Type};
+#[cfg(feature = "repl")]
+use raven::repl;
 
 fn main() {
-    let mut i = Interpreter::new();
-    i.addFunction("print", 1, |_, args|{
-        let text = args.into_iter().map(|e| e.borrow().toString()).collect::<Vec<_>>().join(" ");
-        println!("{}", text);
-        None
-    });
-    i.addFunction("number", 1, |_, args|{
-        Some(Type::Number(args[0].borrow().toString().parse::<f32>().unwrap()).into())
-    });
-    i.addFunction("__add__", 1, |_, args|{
-        Some(Type::Add(args[0].clone(), args[1].clone()))
-    });
-    i.addFunction("__sub__", 1, |_, args|{
-        Some(Type::Subtract(args[0].clone(), args[1].clone()))
-    });
-    i.addFunction("__mul__", 1, |_, args|{
-        Some(Type::Multiply(args[0].clone(), args[1].clone()))
-    });
-    i.addFunction("__div__", 1, |_, args|{
-        Some(Type::Divide(args[0].clone(), args[1].clone()))
-    });
-    i.addFunction("__pow__", 1, |_, args|{
-        Some(Type::Power(args[0].clone(), args[1].clone()))
-    });
-    i.addFunction("__mod__", 1, |_, args|{
-        Some(Type::Modulo(args[0].clone(), args[1].clone()))
-    });
-    i.addFunction("__eq__", 1, |_, args|{
-        Some(Type::Equals(args[0].clone(), args[1].clone()))
-    });
-    i.addFunction("__ne__", 1, |_, args|{
-        Some(Type::NotEquals(args[0].clone(), args[1].clone()))
-    });
-    i.addFunction("__le__", 1, |_, args|{
-        Some(Type::LessThanOrEquals(args[0].clone(), args[1].clone()))
-    });
-    i.addFunction("__ge__", 1, |_, args|{
-        Some(Type::GreaterThanOrEquals(args[0].clone(), args[1].clone()))
-    });
-    i.addFunction("__lt__", 1, |_, args|{
-        Some(Type::LessThan(args[0].clone(), args[1].clone()))
-    });
-    i.addFunction("__gt__", 1, |_, args|{
-        Some(Type::GreaterThan(args[0].clone(), args[1].clone()))
-    });
-    i.addFunction("__and__", 1, |_, args|{
-        Some(Type::And(args[0].clone(), args[1].clone()))
-    });
-    i.addFunction("__or__", 1, |_, args|{
-        Some(Type::Or(args[0].clone(), args[1].clone()))
-    });
-    i.addFunction("__not__", 1, |_, args|{
-        Some(Type::Not(args[0].clone()))
-    });
-
-
-
-
-    i.addObject("true", Type::Bool(true));
-    i.addObject("false", Type::Bool(false));
-
-    // i.addFunction("__if__", 1, |_, args|{
-    //     Some(Type::NotEquals(args[0].clone(), args[1].clone()))
-    // });
-
-    // i.addFunction("__if__", 1, |_, args|{
-    //     let n = args.len();
-
-    //     if args[0].borrow() {
-
-    //     }
-
-
-    //     None
-    // });
-
-
-
-    i.run(read_to_string("src/tests/scripts/basics.rv").unwrap(), true);
-
-
-
-
-
+    let Options { command, debug, vm } = match cli::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}: {}\n\n{}", "error".red().bold(), message, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if vm && !cfg!(feature = "vm") {
+        eprintln!("{}: this raven was built without the vm", "error".red().bold());
+        std::process::exit(2);
+    }
+
+    let path = match &command {
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            return
+        },
+        #[cfg(not(feature = "repl"))]
+        Command::Repl => {
+            eprintln!("{}: this raven was built without the repl", "error".red().bold());
+            std::process::exit(2);
+        },
+        #[cfg(feature = "repl")]
+        Command::Repl => {
+            if let Err(error) = repl::start(vm) {
+                eprintln!("{}: {}", "error".red().bold(), error);
+                std::process::exit(1);
+            }
+            return
+        },
+        Command::Run { file, .. } | Command::Check { file } | Command::Fmt { file } | Command::Ast { file } => file.clone(),
+    };
+    let code = match read_to_string(&path) {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("{}", RavenError::Io(format!("cannot read {}: {}", path, error)).render("", &path));
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(error) = execute(command, &path, &code, debug, vm) {
+        eprintln!("{}", error.render(&code, &path));
+        std::process::exit(1);
+    }
+}
 
-    // let r = RavenParser::ParseFile(r##"print("hello")"##).unwrap();
-    // println!("{:?}", r);
+#[cfg_attr(not(feature = "vm"), allow(unused_variables))]
+fn execute(command: Command, path: &str, code: &str, debug: bool, vm: bool) -> RavenResult<()> {
+    match command {
+        Command::Run { args, .. } => {
+            let mut i = Interpreter::with_stdlib();
+            i.addObject("args", Type::List(args.into_iter().map(|arg| Type::String(arg).wrap()).collect()));
+            // imports are found relative to the script
+            i.addObject("__file__", Type::String(path.to_string()));
+            #[cfg(feature = "vm")]
+            if vm {
+                i.runVM(code.to_string(), debug)?;
+                return Ok(())
+            }
+            i.run(code.to_string(), debug)?;
+        },
+        Command::Check { .. } => {
+            ParseString(code)?;
+        },
+        Command::Fmt { .. } => print!("{}", parser::format::format(&ParseString(code)?)),
+        Command::Ast { .. } => println!("{:#?}", ParseString(code)?),
+        Command::Help | Command::Repl => println!("{}", cli::USAGE),
+    }
+    Ok(())
 }
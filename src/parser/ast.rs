@@ -1,4 +1,4 @@
-use colored::Colorize;
+use crate::color::Colorize;
 use super::parser::Type;
 
 
@@ -7,24 +7,24 @@ fn sep(depth: usize) -> String {
     " ".repeat(SPACE * depth)
 }
 
-fn listArgs(args: &Vec<Type>, sep: &str, br_depth: usize) -> String {
+fn listArgs(args: &[Type], sep: &str, br_depth: usize) -> String {
     args.iter().map(|a| a.to_string(0, br_depth)).collect::<Vec<String>>().join(sep)
 }
 
-fn listBlock(args: &Vec<Type>, depth: usize) -> String {
+fn listBlock(args: &[Type], depth: usize) -> String {
     args.iter().map(|a| format!("{}{}", sep(depth), a.to_string(depth, 0))).collect::<Vec<String>>().join("\n")
 }
 
 
-fn listBoxedBlock(args: &Vec<Box<Type>>, depth: usize) -> String {
+fn listBoxedBlock(args: &[Box<Type>], depth: usize) -> String {
     args.iter().map(|a| format!("{}{}", sep(depth), a.to_string(depth, 0))).collect::<Vec<String>>().join("\n")
 }
 
 
 fn bracket(br: &str, depth: usize) -> String {
-    if (depth + 1) % 2 == 0 {
+    if (depth + 1).is_multiple_of(2) {
         br.magenta()
-    } else if (depth + 1) % 3 == 0{
+    } else if (depth + 1).is_multiple_of(3) {
         br.cyan()
     } else {
         br.yellow()
@@ -35,7 +35,7 @@ fn bracket(br: &str, depth: usize) -> String {
 impl Type {
     pub fn fn_symbol(&self, depth: usize, br_depth: usize) -> String {
         match &self {
-            Type::Symbol(n) => n.to_string(),
+            Type::Symbol(n) | Type::Local { name: n, .. } => n.to_string(),
             _ => self.to_string(depth, br_depth)
         }
     }
@@ -43,6 +43,7 @@ impl Type {
     pub fn to_string(&self, depth: usize, br_depth: usize) -> String {
         match &self {
             Type::Number(n) => n.to_string().yellow().to_string(),
+            Type::Integer(n) => n.to_string().yellow().to_string(),
             Type::Bool(n) => n.to_string().yellow().to_string(),
             Type::Symbol(n) => {
                 if n == "true" || n == "false" {
@@ -51,10 +52,23 @@ impl Type {
                     n.red()
                 }.to_string()
             },
+            Type::Local { name, .. } => name.red().to_string(),
             Type::String(n) => format!("\"{}\"", n).green().to_string(),
-            Type::Call { function, arguments } => {
+            Type::Call { function, arguments, .. } => {
                 let func_name = function.fn_symbol(depth, br_depth);
 
+                match (func_name.as_str(), arguments.as_slice()) {
+                    ("__list__", items) => return format!("{}{}{}", bracket("[", br_depth), listArgs(items, ", ", br_depth+1), bracket("]", br_depth)),
+                    ("__map__", entries) => {
+                        let entries = entries.chunks(2).map(|pair| listArgs(pair, ": ", br_depth+1)).collect::<Vec<_>>().join(", ");
+                        return format!("{}{}{}", bracket("{", br_depth), entries, bracket("}", br_depth))
+                    },
+                    ("__index__", [target, index]) => return format!("{}{}{}{}", target.to_string(depth, br_depth), bracket("[", br_depth), index.to_string(depth, br_depth+1), bracket("]", br_depth)),
+                    ("__member__", [target, Type::String(name)]) => return format!("{}.{}", target.to_string(depth, br_depth), name),
+                    ("__setindex__", [target, index, value]) => return format!("{}{}{}{} = {}", target.to_string(depth, br_depth), bracket("[", br_depth), index.to_string(depth, br_depth+1), bracket("]", br_depth), value.to_string(depth, br_depth)),
+                    _ => {}
+                }
+
                 let op: Option<&str> = match func_name.as_str() {
                     "__add__" => Some(" + "),
                     "__sub__" => Some(" - "),
@@ -70,6 +84,7 @@ impl Type {
                     "__ne__" => Some(" != "),
                     "__and__" => Some(" && "),
                     "__or__" => Some(" || "),
+                    "__range__" => Some(".."),
                     "__not__" => Some("!"),
                     _ => None
                 };
@@ -85,28 +100,47 @@ impl Type {
                 }
 
             },
-            Type::VariableDeclaration { variable, value } => format!("{} {} = {}", "let".purple(), variable.to_string(depth, br_depth), value.to_string(depth, br_depth)),
-            Type::Assignment { variable, value } => format!("{} = {}", variable.to_string(depth, br_depth), value.to_string(depth, br_depth)),
-            Type::CreateFunction { name, code, parameters } => {
+            Type::VariableDeclaration { variable, value, .. } => format!("{} {} = {}", "let".purple(), variable.to_string(depth, br_depth), value.to_string(depth, br_depth)),
+            Type::Assignment { variable, value, .. } => format!("{} = {}", variable.to_string(depth, br_depth), value.to_string(depth, br_depth)),
+            Type::CreateFunction { name, code, parameters, .. } => {
                 let name = name.fn_symbol(depth, br_depth);
-                if name == "" {
+                if name.is_empty() {
                     format!("{}{}{} {} {}\n{}\n{}{}", bracket("(", br_depth), parameters.iter().map(|p| p.red().to_string()).collect::<Vec<String>>().join(", "),bracket(")", br_depth), "=>", bracket("{", depth),  listBoxedBlock(code, depth + 1), sep(depth), bracket("}", depth))
                 } else {
                     format!("{} {}{}{}{} {}\n{}\n{}{}", "fn".purple(), name.blue(), bracket("(", br_depth), parameters.iter().map(|p| p.red().to_string()).collect::<Vec<String>>().join(", "),bracket(")", br_depth), bracket("{", depth), listBoxedBlock(code, depth + 1), sep(depth), bracket("}", depth))
                 }
             },
-            Type::Conditional { condition, then, otherwise } => {
+            Type::Conditional { condition, then, otherwise, .. } => {
                 let first = format!("{} {} {}\n{}\n{}{}", "if".purple(),  condition.to_string(depth, br_depth), bracket("{", depth), listBlock(then, depth + 1), sep(depth), bracket("}", depth));
                 if let Some(other) = otherwise {
                     return format!("{} {} {}\n{}\n{}{}", first, "else".purple(), bracket("{", depth), listBlock(other, depth+1), sep(depth), bracket("}", depth))
                 } 
-                return first;
+                first
+            },
+            Type::While { condition, code, .. } => format!("{} {} {}\n{}\n{}{}", "while".purple(), condition.to_string(depth, br_depth), bracket("{", depth), listBlock(code, depth + 1), sep(depth), bracket("}", depth)),
+            Type::For { variable, iterable, code, .. } => format!("{} {} {} {} {}\n{}\n{}{}", "for".purple(), variable.red(), "in".purple(), iterable.to_string(depth, br_depth), bracket("{", depth), listBlock(code, depth + 1), sep(depth), bracket("}", depth)),
+            Type::Return { value, .. } => match value {
+                Some(value) => format!("{} {}", "return".purple(), value.to_string(depth, br_depth)),
+                None => "return".purple().to_string()
             },
-            Type::While { condition, code } => format!("{} {} {}\n{}\n{}{}", "while".purple(), condition.to_string(depth, br_depth), bracket("{", depth), listBlock(code, depth + 1), sep(depth), bracket("}", depth)),
+            Type::Break { .. } => "break".purple().to_string(),
+            Type::Continue { .. } => "continue".purple().to_string(),
             Type::Comment(comment) => format!("//{}", comment).bright_black().to_string(),
-            // Type::Function(_) => todo!(),
-            // Type::Invocation { code } => todo!(),
-            _ => format!("{:?}", self)
+            Type::Blank => String::new(),
+            Type::List(items) => {
+                let items = items.iter().map(|item| item.borrow().to_string(depth, br_depth + 1)).collect::<Vec<_>>().join(", ");
+                format!("{}{}{}", bracket("[", br_depth), items, bracket("]", br_depth))
+            },
+            Type::Map(entries) => {
+                let entries = entries.iter().map(|(key, value)| format!("{}: {}", format!("\"{}\"", key).green(), value.borrow().to_string(depth, br_depth + 1))).collect::<Vec<_>>().join(", ");
+                format!("{}{}{}", bracket("{", br_depth), entries, bracket("}", br_depth))
+            },
+            Type::Range(start, end) => format!("{}..{}", start, end).yellow().to_string(),
+            Type::Function(function) => match function.parameters() {
+                Some(parameters) => format!("{}{}{}{}", "fn".purple(), bracket("(", br_depth), parameters.iter().map(|p| p.red().to_string()).collect::<Vec<_>>().join(", "), bracket(")", br_depth)),
+                None => format!("{}{}", "fn".purple(), "(builtin)".bright_black()),
+            },
+            Type::Invocation { code } => code.iter().map(|line| line.to_string(depth, br_depth)).collect::<Vec<_>>().join(&format!("\n{}", sep(depth))),
         }
     }
 }
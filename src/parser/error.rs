@@ -0,0 +1,120 @@
+use std::fmt::Display;
+
+use peg::{error::ParseError, str::LineCol};
+
+use super::span::Span;
+
+/// a parse failure, with peg's expected tokens cleaned up for humans
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError{
+    pub span: Span,
+    pub expected: Vec<String>,
+    pub message: String,
+}
+
+impl Display for SyntaxError{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl SyntaxError{
+    pub fn new(source: &str, error: ParseError<LineCol>) -> SyntaxError{
+        let offset = error.location.offset;
+        let mut expected = error.expected.tokens().filter_map(token).collect::<Vec<_>>();
+        expected.sort();
+        expected.dedup();
+
+        let found = match source[offset..].chars().next() {
+            Some(c) => format!("`{}`", c.escape_default()),
+            None => "end of file".to_string()
+        };
+        let has = |token: &str| expected.iter().any(|e| e == token);
+        let opened = |bracket: char| unclosed(source, offset, bracket).map(|at| {
+            let (line, column) = Span::lineCol(source, at);
+            format!("{}:{}", line, column)
+        });
+
+        if expected == ["number"] {
+            // the number rule matched its characters but they did not parse as a number
+            let start = source[..offset].trim_end_matches(|c: char| c.is_ascii_digit() || c == '.' || c == '-').len();
+            let message = format!("invalid number `{}`", &source[start..offset]);
+            return SyntaxError { span: Span::new(start, offset), expected, message }
+        }
+
+        let message = if has("string character") {
+            let start = source[..offset].rfind('"').unwrap_or(offset);
+            let (line, column) = Span::lineCol(source, start);
+            format!("unterminated string opened at {}:{}", line, column)
+        } else if let Some(at) = opened('{').filter(|_| has("}")) {
+            format!("expected `}}` to close block opened at {}, found {}", at, found)
+        } else if let Some(at) = opened('(').filter(|_| has(")")) {
+            format!("expected `)` to close `(` opened at {}, found {}", at, found)
+        } else if has("end of file") {
+            format!("unexpected {}", found)
+        } else if has("identifier") && has("number") {
+            format!("expected an expression, found {}", found)
+        } else {
+            format!("expected {}, found {}", list(&expected), found)
+        };
+
+        SyntaxError { span: Span::new(offset, offset + 1), expected, message }
+    }
+}
+
+/// turns one of peg's expected tokens into something readable, dropping the ones that only add noise
+fn token(raw: &'static str) -> Option<String>{
+    if raw.starts_with('"') {
+        let literal = &raw[1..raw.len() - 1];
+        return match literal {
+            "\\n" | "//" | "." => None,
+            literal => Some(literal.replace("\\\"", "\"")),
+        }
+    }
+    match raw {
+        "EOF" => Some("end of file".to_string()),
+        // reasons given by `{? }` actions that backtracked on purpose
+        "pipe" | "index" => None,
+        "['A'..='Z' | 'a'..='z' | '_']" => Some("identifier".to_string()),
+        "['0'..='9' | '-']" => Some("number".to_string()),
+        "[^ '\"']" => Some("string character".to_string()),
+        raw if raw.contains("' '") || raw.contains("'\\n'") => None,
+        raw => Some(raw.to_string()),
+    }
+}
+
+fn list(expected: &[String]) -> String{
+    let quoted = expected.iter().map(|e| format!("`{}`", e)).collect::<Vec<_>>();
+    match quoted.as_slice() {
+        [] => "something else".to_string(),
+        [one] => one.clone(),
+        [rest @ .., last] => format!("one of {} or {}", rest.join(", "), last),
+    }
+}
+
+/// offset of the innermost `open` bracket left unclosed before `offset`, skipping strings and comments
+fn unclosed(source: &str, offset: usize, open: char) -> Option<usize>{
+    let mut stack = vec![];
+    let mut chars = source[..offset].char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => {
+                for (_, c) in chars.by_ref() {
+                    if c == '"' { break }
+                }
+            },
+            '/' if matches!(chars.peek(), Some((_, '/'))) => {
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' { break }
+                }
+            },
+            '{' | '(' => stack.push((c, i)),
+            '}' | ')' => { stack.pop(); },
+            _ => {}
+        }
+    }
+    match stack.last() {
+        Some((c, i)) if *c == open => Some(*i),
+        _ => None
+    }
+}
@@ -0,0 +1,271 @@
+use std::borrow::Borrow;
+
+use super::parser::Type;
+
+const INDENT: &str = "    ";
+
+/// how tightly a piece of source binds, following the levels of `Operation` and `Arithmetic` in the grammar
+type Level = u8;
+/// only valid on its own, as a line or an argument: declarations, assignments, functions, `return`
+const STATEMENT: Level = 0;
+const NOT: Level = 6;
+/// the first level of `Arithmetic`, nothing below it can be put in parentheses inside arithmetic
+const SUM: Level = 7;
+const ATOM: Level = 10;
+
+const BINARY: [(&str, &str, Level); 15] = [
+    ("__and__", "&&", 1), ("__or__", "||", 1),
+    ("__eq__", "==", 2), ("__ne__", "!=", 2),
+    ("__le__", "<=", 3), ("__ge__", ">=", 3),
+    ("__lt__", "<", 4), ("__gt__", ">", 4),
+    ("__range__", "..", 5),
+    ("__add__", "+", 7), ("__sub__", "-", 7),
+    ("__mul__", "*", 8), ("__div__", "/", 8),
+    ("__pow__", "**", 9), ("__mod__", "%", 9),
+];
+
+const UPDATES: [(&str, &str); 6] = [("__add__", "+="), ("__sub__", "-="), ("__mul__", "*="), ("__div__", "/="), ("__pow__", "**="), ("__mod__", "%=")];
+
+struct Source{
+    text: String,
+    level: Level,
+    /// can be followed by `[i]`: symbols, strings, calls, lists, maps and other indexing
+    indexable: bool,
+    /// how `if`, `while` and `for` are written inside an expression, which is not quite like on their own
+    inline: Option<String>,
+}
+
+impl Source{
+    fn new(text: String, level: Level) -> Source{
+        Source { text, level, indexable: false, inline: None }
+    }
+
+    fn indexable(text: String) -> Source{
+        Source { text, level: ATOM, indexable: true, inline: None }
+    }
+
+    fn block(text: String, inline: String) -> Source{
+        Source { text, level: ATOM, indexable: false, inline: Some(inline) }
+    }
+}
+
+/// prints `code` back as source in the canonical style: four space indents, one statement per line,
+/// spaces around operators and only the parentheses the grammar needs. comments and single empty
+/// lines between statements are kept, and parsing the result gives back the same tree
+pub fn format(code: &[Type]) -> String{
+    lines(code, 0).into_iter().map(|line| line + "\n").collect()
+}
+
+fn lines<T: Borrow<Type>>(code: &[T], depth: usize) -> Vec<String>{
+    let mut lines: Vec<String> = vec![];
+    let mut previous: Option<usize> = None;
+    for node in code.iter().map(Borrow::borrow) {
+        let text = source(node, depth).text;
+        if text.is_empty() {
+            lines.push(text);
+            continue
+        }
+        // a line starting with `(` or a negative number would otherwise continue the one before,
+        // as a call of the symbol it ends with or as a subtraction
+        if let Some(previous) = previous.filter(|_| text.starts_with(['(', '-'])) {
+            lines[previous].push(';');
+        }
+        if !matches!(node, Type::Comment(_)) {
+            previous = Some(lines.len());
+        }
+        lines.push(format!("{}{}", INDENT.repeat(depth), text));
+    }
+    lines
+}
+
+fn block<T: Borrow<Type>>(code: &[T], depth: usize) -> String{
+    if code.is_empty() {
+        return "{}".to_string()
+    }
+    format!("{{\n{}\n{}}}", lines(code, depth + 1).join("\n"), INDENT.repeat(depth))
+}
+
+fn list(items: &[Type], depth: usize) -> String{
+    items.iter().map(|item| source(item, depth).text).collect::<Vec<_>>().join(", ")
+}
+
+/// `node` as an operand that has to bind at least as tightly as `level`, or `None` if no amount of
+/// parentheses makes it one
+fn operand(node: &Type, level: Level, depth: usize) -> Option<String>{
+    let source = source(node, depth);
+    if let Some(inline) = source.inline {
+        Some(format!("({})", inline))
+    } else if let (STATEMENT, Type::Call { function, arguments, .. }) = (source.level, node) {
+        // `xs[i] = v` cannot be an operand, but the call it stands for can
+        Some(plain(&function.toString(), arguments, depth))
+    } else if source.level >= level {
+        Some(source.text)
+    } else if source.level == STATEMENT || (level >= SUM && source.level < SUM) {
+        None
+    } else {
+        Some(format!("({})", source.text))
+    }
+}
+
+/// the condition of an `if` or `while` on its own and inside an expression. the grammar takes a
+/// parenthesis right after the keyword as the optional pair around the whole condition, so a
+/// condition starting with one gets wrapped. inside an expression `if (` reads as a call of `if`
+/// instead, there the condition avoids the parenthesis by spelling out the call
+fn condition(node: &Type, depth: usize) -> (String, String){
+    if let Some(inline) = source(node, depth).inline {
+        return (inline.clone(), inline)
+    }
+    let text = operand(node, 1, depth).unwrap_or_else(|| source(node, depth).text);
+    match node {
+        Type::Call { function, arguments, .. } if text.starts_with('(') => (format!("({})", text), plain(&function.toString(), arguments, depth)),
+        _ => (text.clone(), text),
+    }
+}
+
+fn float(number: f32) -> String{
+    // without a decimal point the literal would come back as an integer
+    let text = number.to_string();
+    if text.contains('.') { text } else { format!("{}.0", text) }
+}
+
+fn call(name: &str, arguments: &[Type], depth: usize) -> Source{
+    if let Some((_, symbol, level)) = BINARY.iter().find(|(function, ..)| *function == name) {
+        if let [x, y] = arguments {
+            if let (Some(x), Some(y)) = (operand(x, *level, depth), operand(y, level + 1, depth)) {
+                let text = if *symbol == ".." { format!("{}..{}", x, y) } else { format!("{} {} {}", x, symbol, y) };
+                return Source::new(text, *level)
+            }
+        }
+    }
+
+    let indexable = |target: &Type| {
+        let target = source(target, depth);
+        Some(target.text).filter(|_| target.indexable)
+    };
+    match (name, arguments) {
+        ("__not__", [x]) => if let Some(x) = operand(x, NOT, depth) {
+            return Source::new(format!("!{}", x), NOT)
+        },
+        ("__list__", items) => return Source::indexable(format!("[{}]", list(items, depth))),
+        ("__map__", entries) if entries.len() % 2 == 0 => {
+            let entries = entries.chunks(2).map(|pair| format!("{}: {}", source(&pair[0], depth).text, source(&pair[1], depth).text));
+            return Source::indexable(format!("{{{}}}", entries.collect::<Vec<_>>().join(", ")))
+        },
+        ("__index__", [target, index]) => if let Some(target) = indexable(target) {
+            return Source::indexable(format!("{}[{}]", target, source(index, depth).text))
+        },
+        ("__member__", [target, Type::String(member)]) if identifier(member) => if let Some(target) = indexable(target) {
+            return Source::indexable(format!("{}.{}", target, member))
+        },
+        ("__setindex__", [target, index, value]) => if let Some(target) = indexable(target) {
+            return Source::new(format!("{}[{}] = {}", target, source(index, depth).text, source(value, depth).text), STATEMENT)
+        },
+        _ => {}
+    }
+    // anything the sugar cannot express is still a plain call of the builtin
+    Source::indexable(plain(name, arguments, depth))
+}
+
+fn identifier(name: &str) -> bool{
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// the path and, for `from`, the name of what `node` imports if it is what an import lowers to
+fn import(node: &Type) -> Option<(&str, Option<&str>)>{
+    let Type::VariableDeclaration { variable, value, .. } = node else { return None };
+    let (Type::Symbol(variable), Type::Call { function, arguments, .. }) = (&**variable, &**value) else { return None };
+    match (function.toString().as_str(), arguments.as_slice()) {
+        ("__import__", [Type::String(path)]) => Some((path, None)),
+        ("__import__", [Type::String(path), Type::String(name)]) if name == variable => Some((path, Some(name))),
+        _ => None
+    }
+}
+
+fn plain(name: &str, arguments: &[Type], depth: usize) -> String{
+    format!("{}({})", name, list(arguments, depth))
+}
+
+fn assignment(variable: &Type, value: &Type, depth: usize) -> String{
+    if let (Type::Symbol(name), Type::Call { function, arguments, .. }) = (variable, value) {
+        if let (Type::Symbol(function), [Type::Symbol(target), amount]) = (&**function, arguments.as_slice()) {
+            match (function.as_str(), amount) {
+                _ if target != name => {},
+                ("__add__", Type::Integer(1)) => return format!("{}++", name),
+                ("__sub__", Type::Integer(1)) => return format!("{}--", name),
+                (function, amount) => if let Some((_, update)) = UPDATES.iter().find(|(name, _)| *name == function) {
+                    if let Some(amount) = operand(amount, 1, depth) {
+                        return format!("{} {} {}", name, update, amount)
+                    }
+                }
+            }
+        }
+    }
+    format!("{} = {}", source(variable, depth).text, source(value, depth).text)
+}
+
+fn source(node: &Type, depth: usize) -> Source{
+    match node {
+        Type::Number(number) => Source::new(float(*number), ATOM),
+        Type::Integer(number) => Source::new(number.to_string(), ATOM),
+        Type::Bool(value) => Source::new(value.to_string(), ATOM),
+        Type::Symbol(name) => Source::indexable(name.clone()),
+        Type::String(text) => Source::indexable(format!("\"{}\"", text)),
+        Type::Call { function, arguments, .. } => match &**function {
+            Type::Symbol(name) => call(name, arguments, depth),
+            callee => Source::indexable(format!("{}({})", source(callee, depth).text, list(arguments, depth))),
+        },
+        Type::VariableDeclaration { variable, .. } if import(node).is_some_and(|(_, name)| name.is_none()) => {
+            let (path, _) = import(node).unwrap();
+            Source::new(format!("import \"{}\" as {}", path, variable.toString()), STATEMENT)
+        },
+        Type::VariableDeclaration { variable, value, .. } => {
+            Source::new(format!("let {} = {}", source(variable, depth).text, source(value, depth).text), STATEMENT)
+        },
+        Type::Assignment { variable, value, .. } => Source::new(assignment(variable, value, depth), STATEMENT),
+        Type::CreateFunction { name, code, parameters, .. } => {
+            let text = match name.toString().as_str() {
+                "" => format!("({}) => {}", parameters.join(", "), block(code, depth)),
+                name => format!("fn {}({}) {}", name, parameters.join(", "), block(code, depth)),
+            };
+            Source::new(text, STATEMENT)
+        },
+        Type::Conditional { condition: test, then, otherwise, .. } => {
+            let (test, inline) = condition(test, depth);
+            let rest = match otherwise.as_deref() {
+                Some([elif @ Type::Conditional { .. }]) => format!(" else {}", source(elif, depth).text),
+                Some(otherwise) => format!(" else {}", block(otherwise, depth)),
+                None => String::new(),
+            };
+            let then = block(then, depth);
+            Source::block(format!("if {} {}{}", test, then, rest), format!("if {} {}{}", inline, then, rest))
+        },
+        Type::While { condition: test, code, .. } => {
+            let (test, inline) = condition(test, depth);
+            let code = block(code, depth);
+            Source::block(format!("while {} {}", test, code), format!("while {} {}", inline, code))
+        },
+        Type::For { variable, iterable, code, .. } => {
+            let iterable = operand(iterable, 1, depth).unwrap_or_else(|| source(iterable, depth).text);
+            let text = format!("for {} in {} {}", variable, iterable, block(code, depth));
+            Source::block(text.clone(), text)
+        },
+        Type::Return { value: Some(value), .. } => Source::new(format!("return {}", source(value, depth).text), STATEMENT),
+        Type::Return { value: None, .. } => Source::new("return".to_string(), STATEMENT),
+        Type::Break { .. } => Source::new("break".to_string(), STATEMENT),
+        Type::Continue { .. } => Source::new("continue".to_string(), STATEMENT),
+        Type::Comment(text) => Source::new(format!("//{}", text), STATEMENT),
+        Type::Blank => Source::new(String::new(), STATEMENT),
+        Type::Invocation { code } if !code.is_empty() => {
+            let imports = code.iter().map(import).collect::<Option<Vec<_>>>().unwrap_or_default();
+            match imports.as_slice() {
+                [(path, Some(_)), ..] if imports.len() == code.len() && imports.iter().all(|(from, name)| from == path && name.is_some()) => {
+                    let names = imports.iter().filter_map(|(_, name)| *name).collect::<Vec<_>>();
+                    Source::new(format!("from \"{}\" import {}", path, names.join(", ")), STATEMENT)
+                },
+                _ => Source::new(node.toString(), STATEMENT),
+            }
+        },
+        // values only appear in trees built at runtime, they have no syntax of their own
+        other => Source::new(other.toString(), STATEMENT),
+    }
+}
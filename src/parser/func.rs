@@ -1,108 +1,293 @@
-use crate::interpreter::interpreter::Object;
+use std::cmp::Ordering;
+
+use crate::interpreter::{interpreter::Object, error::{RavenError, RavenResult}};
 
 use super::parser::Type;
 
+/// a pair of numeric operands. integers stay exact as long as both sides are integers,
+/// as soon as either side is a float the other one is promoted and the result is a float
+pub(crate) enum Numeric{
+    Integers(i64, i64),
+    Floats(f64, f64),
+}
+
+pub(crate) fn numeric(x: &Type, y: &Type) -> Option<Numeric>{
+    match (x, y) {
+        (Type::Integer(x), Type::Integer(y)) => Some(Numeric::Integers(*x, *y)),
+        (Type::Integer(x), Type::Number(y)) => Some(Numeric::Floats(*x as f64, *y as f64)),
+        (Type::Number(x), Type::Integer(y)) => Some(Numeric::Floats(*x as f64, *y as f64)),
+        (Type::Number(x), Type::Number(y)) => Some(Numeric::Floats(*x as f64, *y as f64)),
+        _ => None
+    }
+}
+
+impl Numeric{
+    fn apply(self, integers: impl Fn(i64, i64) -> RavenResult<Option<i64>>, floats: impl Fn(f64, f64) -> f64) -> RavenResult<Type>{
+        match self {
+            Numeric::Integers(x, y) => match integers(x, y)? {
+                Some(result) => Ok(Type::Integer(result)),
+                None => Err(RavenError::Runtime("integer overflow".to_string())),
+            },
+            Numeric::Floats(x, y) => Ok(Type::Number(floats(x, y) as f32)),
+        }
+    }
+
+    pub(crate) fn add(self) -> RavenResult<Type>{
+        self.apply(|x, y| Ok(x.checked_add(y)), |x, y| x + y)
+    }
+
+    pub(crate) fn subtract(self) -> RavenResult<Type>{
+        self.apply(|x, y| Ok(x.checked_sub(y)), |x, y| x - y)
+    }
+
+    pub(crate) fn multiply(self) -> RavenResult<Type>{
+        self.apply(|x, y| Ok(x.checked_mul(y)), |x, y| x * y)
+    }
+
+    /// integer division rounds towards negative infinity, so `7 / 2` is `3` and `-7 / 2` is `-4`.
+    /// dividing an integer by zero is an error, floats follow IEEE and give infinity or NaN
+    pub(crate) fn divide(self) -> RavenResult<Type>{
+        self.apply(|x, y| {
+            if y == 0 {
+                return Err(RavenError::Runtime("division by zero".to_string()))
+            }
+            Ok(x.checked_div(y).map(|quotient| if x % y != 0 && (x < 0) != (y < 0) { quotient - 1 } else { quotient }))
+        }, |x, y| x / y)
+    }
+
+    /// an integer raised to a non-negative integer stays exact, anything else is a float
+    pub(crate) fn power(self) -> RavenResult<Type>{
+        match self {
+            Numeric::Integers(base, exponent) if exponent < 0 => Numeric::Floats(base as f64, exponent as f64).apply(|_, _| Ok(None), f64::powf),
+            pair => pair.apply(|x, y| Ok(u32::try_from(y).ok().and_then(|y| x.checked_pow(y))), f64::powf),
+        }
+    }
+
+    /// the remainder of the rounded down division, so it always has the sign of the divisor
+    /// and `x == (x / y) * y + x % y` holds for integers. floats use the same rule
+    pub(crate) fn modulo(self) -> RavenResult<Type>{
+        self.apply(|x, y| {
+            if y == 0 {
+                return Err(RavenError::Runtime("division by zero".to_string()))
+            }
+            let remainder = x.wrapping_rem(y);
+            Ok(Some(if remainder != 0 && (remainder < 0) != (y < 0) { remainder + y } else { remainder }))
+        }, |x, y| x - y * (x / y).floor())
+    }
+
+    /// `None` only when a float is NaN
+    pub(crate) fn order(&self) -> Option<Ordering>{
+        match self {
+            Numeric::Integers(x, y) => Some(x.cmp(y)),
+            Numeric::Floats(x, y) => x.partial_cmp(y),
+        }
+    }
+}
+
+/// equality as `==` sees it: integers equal the floats with the same value, even inside lists and maps
+pub fn equal(x: &Type, y: &Type) -> bool{
+    if let Some(pair) = numeric(x, y) {
+        return pair.order() == Some(Ordering::Equal)
+    }
+    match (x, y) {
+        (Type::List(x), Type::List(y)) => x.len() == y.len() && x.iter().zip(y).all(|(x, y)| equal(&x.borrow(), &y.borrow())),
+        (Type::Map(x), Type::Map(y)) => x.len() == y.len() && x.iter().all(|(key, x)| y.get(key).is_some_and(|y| equal(&x.borrow(), &y.borrow()))),
+        (x, y) => x == y
+    }
+}
+
+fn compare(x: Object, y: Object, test: fn(Ordering) -> bool) -> RavenResult<Object>{
+    match numeric(&x.borrow(), &y.borrow()) {
+        Some(pair) => Ok(Type::Bool(pair.order().is_some_and(test)).wrap()),
+        None => Err(RavenError::mismatch("compare", &[&x.borrow(), &y.borrow()])),
+    }
+}
+
 impl Type{
-    pub fn Add(x: Object, y: Object) -> Object{
+    pub fn Add(x: Object, y: Object) -> RavenResult<Object>{
+        if let Some(pair) = numeric(&x.borrow(), &y.borrow()) {
+            return pair.add().map(Type::wrap)
+        }
         match (&*x.borrow(),&*y.borrow()){
-            (Type::Number(x), Type::Number(y)) => {Type::Number(*x+*y).wrap()},
-            (Type::String(x), Type::String(y)) => {Type::String(x.to_owned()+y).wrap()},
-            (Type::String(x), Type::Number(y)) => {Type::String(x.to_owned()+&format!("{}", y)).wrap()},
-            (Type::Number(x), Type::String(y)) => {Type::String(format!("{}", x) + y).wrap()},
-            _ => panic!("attempted to Add {:?} with {:?}",x,y),
+            (Type::String(x), Type::String(y)) => Ok(Type::String(x.to_owned()+y).wrap()),
+            (Type::String(x), y @ (Type::Number(_) | Type::Integer(_))) => Ok(Type::String(x.to_owned()+&y.toString()).wrap()),
+            (x @ (Type::Number(_) | Type::Integer(_)), Type::String(y)) => Ok(Type::String(x.toString() + y).wrap()),
+            (Type::List(x), Type::List(y)) => Ok(Type::List(x.iter().chain(y).cloned().collect()).wrap()),
+            (x, y) => Err(RavenError::mismatch("add", &[x, y])),
         }
     }
-    pub fn Subtract(x: Object, y: Object) -> Object{
-        match (&*x.borrow(),&*y.borrow()){
-            (Type::Number(x), Type::Number(y)) => {Type::Number(*x-*y).wrap()},
-            _ => panic!("attempted to Subtract {:?} with {:?}",x,y),
+    pub fn Subtract(x: Object, y: Object) -> RavenResult<Object>{
+        match numeric(&x.borrow(), &y.borrow()) {
+            Some(pair) => pair.subtract().map(Type::wrap),
+            None => Err(RavenError::mismatch("subtract", &[&x.borrow(), &y.borrow()])),
         }
     }
-    pub fn Multiply(x: Object, y: Object) -> Object{
+    pub fn Multiply(x: Object, y: Object) -> RavenResult<Object>{
+        if let Some(pair) = numeric(&x.borrow(), &y.borrow()) {
+            return pair.multiply().map(Type::wrap)
+        }
         match (&*x.borrow(),&*y.borrow()){
-            (Type::Number(x), Type::Number(y)) => {Type::Number(*x * *y).wrap()},
-            (Type::String(x), Type::Number(y)) => {Type::String(x.repeat(*y as usize)).wrap()},
-            (Type::Number(x), Type::String(y)) => {Type::String(y.repeat(*x as usize)).wrap()},
-            _ => panic!("attempted to Multiply {:?} with {:?}",x,y),
+            (Type::String(text), Type::Integer(times)) | (Type::Integer(times), Type::String(text)) => {
+                let times = usize::try_from(*times).map_err(|_| RavenError::Runtime(format!("cannot repeat a string {} times", times)))?;
+                Ok(Type::String(text.repeat(times)).wrap())
+            },
+            (x, y) => Err(RavenError::mismatch("multiply", &[x, y])),
         }
     }
-    pub fn Divide(x: Object, y: Object) -> Object{
-        match (&*x.borrow(),&*y.borrow()){
-            (Type::Number(x), Type::Number(y)) => {Type::Number(*x / *y).wrap()},
-            _ => panic!("attempted to Divide {:?} with {:?}",x,y),
+
+    /// see `Numeric::divide`
+    pub fn Divide(x: Object, y: Object) -> RavenResult<Object>{
+        match numeric(&x.borrow(), &y.borrow()) {
+            Some(pair) => pair.divide().map(Type::wrap),
+            None => Err(RavenError::mismatch("divide", &[&x.borrow(), &y.borrow()])),
         }
     }
 
-    pub fn Power(x: Object, y: Object) -> Object{
-        match (&*x.borrow(),&*y.borrow()){
-            (Type::Number(x), Type::Number(y)) => {Type::Number((*x).powf(*y)).wrap()},
-            _ => panic!("attempted to Divide {:?} with {:?}",x,y),
+    /// see `Numeric::power`
+    pub fn Power(x: Object, y: Object) -> RavenResult<Object>{
+        match numeric(&x.borrow(), &y.borrow()) {
+            Some(pair) => pair.power().map(Type::wrap),
+            None => Err(RavenError::mismatch("raise", &[&x.borrow(), &y.borrow()])),
         }
     }
 
-    pub fn Modulo(x: Object, y: Object) -> Object{
-        match (&*x.borrow(),&*y.borrow()){
-            (Type::Number(x), Type::Number(y)) => {Type::Number(*x % *y).wrap()},
-            _ => panic!("attempted to Modulo {:?} with {:?}",x,y),
+    /// see `Numeric::modulo`
+    pub fn Modulo(x: Object, y: Object) -> RavenResult<Object>{
+        match numeric(&x.borrow(), &y.borrow()) {
+            Some(pair) => pair.modulo().map(Type::wrap),
+            None => Err(RavenError::mismatch("modulo", &[&x.borrow(), &y.borrow()])),
         }
     }
 
-    pub fn Equals(x: Object, y: Object) -> Object{
-        return Type::Bool(*x.borrow() == *y.borrow()).wrap()
+    pub fn Equals(x: Object, y: Object) -> RavenResult<Object>{
+        Ok(Type::Bool(equal(&x.borrow(), &y.borrow())).wrap())
     }
 
-    pub fn NotEquals(x: Object, y: Object) -> Object{
-        return Type::Bool(*x.borrow() != *y.borrow()).wrap()
+    pub fn NotEquals(x: Object, y: Object) -> RavenResult<Object>{
+        Ok(Type::Bool(!equal(&x.borrow(), &y.borrow())).wrap())
     }
 
-    pub fn LessThanOrEquals(x: Object, y: Object) -> Object{
+    pub fn LessThanOrEquals(x: Object, y: Object) -> RavenResult<Object>{
+        compare(x, y, Ordering::is_le)
+    }
+
+    pub fn GreaterThanOrEquals(x: Object, y: Object) -> RavenResult<Object>{
+        compare(x, y, Ordering::is_ge)
+    }
+
+    pub fn LessThan(x: Object, y: Object) -> RavenResult<Object>{
+        compare(x, y, Ordering::is_lt)
+    }
+
+    pub fn GreaterThan(x: Object, y: Object) -> RavenResult<Object>{
+        compare(x, y, Ordering::is_gt)
+    }
+
+    pub fn And(x: Object, y: Object) -> RavenResult<Object>{
         match (&*x.borrow(),&*y.borrow()){
-            (Type::Number(x), Type::Number(y)) => {Type::Bool(*x <= *y).wrap()},
-            _ => panic!("attempted to compare {:?} with {:?}",x,y),
+            (Type::Bool(x), Type::Bool(y)) => Ok(Type::Bool(*x && *y).wrap()),
+            (x, y) => Err(RavenError::mismatch("and", &[x, y])),
         }
     }
 
-    pub fn GreaterThanOrEquals(x: Object, y: Object) -> Object{
+    pub fn Or(x: Object, y: Object) -> RavenResult<Object>{
         match (&*x.borrow(),&*y.borrow()){
-            (Type::Number(x), Type::Number(y)) => {Type::Bool(*x >= *y).wrap()},
-            _ => panic!("attempted to compare {:?} with {:?}",x,y),
+            (Type::Bool(x), Type::Bool(y)) => Ok(Type::Bool(*x || *y).wrap()),
+            (x, y) => Err(RavenError::mismatch("or", &[x, y])),
         }
     }
 
-    pub fn LessThan(x: Object, y: Object) -> Object{
-        match (&*x.borrow(),&*y.borrow()){
-            (Type::Number(x), Type::Number(y)) => {Type::Bool(*x < *y).wrap()},
-            _ => panic!("attempted to compare {:?} with {:?}",x,y),
+    /// `xs[i]`, returning the element itself rather than a copy
+    pub fn Index(x: Object, i: Object) -> RavenResult<Object>{
+        match (&*x.borrow(), &*i.borrow()){
+            (Type::List(items), i @ (Type::Integer(_) | Type::Number(_))) => Ok(items[position(i, items.len())?].clone()),
+            (Type::String(text), i @ (Type::Integer(_) | Type::Number(_))) => {
+                let at = position(i, text.chars().count())?;
+                Ok(Type::String(text.chars().nth(at).unwrap().to_string()).wrap())
+            },
+            (Type::Map(entries), Type::String(key)) => entries.get(key).cloned().ok_or_else(|| RavenError::missing(key)),
+            (x, i) => Err(RavenError::mismatch("index", &[x, i])),
         }
     }
 
-    pub fn GreaterThan(x: Object, y: Object) -> Object{
-        match (&*x.borrow(),&*y.borrow()){
-            (Type::Number(x), Type::Number(y)) => {Type::Bool(*x > *y).wrap()},
-            _ => panic!("attempted to compare {:?} with {:?}",x,y),
+    /// `m.name`, looking up a map entry or a module's export by name
+    pub fn Member(x: Object, name: &str) -> RavenResult<Object>{
+        match &*x.borrow(){
+            Type::Map(entries) => entries.get(name).cloned().ok_or_else(|| RavenError::missing(name)),
+            x => Err(RavenError::mismatch("access a member of", &[x])),
         }
     }
 
-    pub fn And(x: Object, y: Object) -> Object{
-        match (&*x.borrow(),&*y.borrow()){
-            (Type::Bool(x), Type::Bool(y)) => {Type::Bool(*x && *y).wrap()},
-            _ => panic!("attempted to And {:?} with {:?}",x,y),
+    /// `xs[i] = value` or `m[key] = value`, changing the collection in place so every holder of it sees the change
+    pub fn SetIndex(x: Object, i: Object, value: Object) -> RavenResult<()>{
+        // copy the index out before borrowing the target mutably, they may be the same object
+        let index = i.borrow().clone();
+        match (&mut *x.borrow_mut(), index){
+            (Type::List(items), i @ (Type::Integer(_) | Type::Number(_))) => {
+                let at = position(&i, items.len())?;
+                items[at] = value;
+                Ok(())
+            },
+            (Type::Map(entries), Type::String(key)) => {
+                entries.insert(key, value);
+                Ok(())
+            },
+            (x, i) => Err(RavenError::mismatch("assign into", &[x, &i])),
         }
     }
 
-    pub fn Or(x: Object, y: Object) -> Object{
+    pub fn MakeRange(x: Object, y: Object) -> RavenResult<Object>{
         match (&*x.borrow(),&*y.borrow()){
-            (Type::Bool(x), Type::Bool(y)) => {Type::Bool(*x || *y).wrap()},
-            _ => panic!("attempted to Or {:?} with {:?}",x,y),
+            (Type::Integer(x), Type::Integer(y)) => Ok(Type::Range(*x, *y).wrap()),
+            (x, y) => Err(RavenError::mismatch("make a range from", &[x, y])),
         }
     }
 
-    pub fn Not(x: Object) -> Object{
+    /// what a `for` loop over `x` steps through with `Nth`, taken once when the loop starts. a string
+    /// becomes the list of its characters and a map the list of its keys, so each step is a lookup
+    /// rather than a scan from the start
+    pub fn Iterable(x: Object) -> RavenResult<Object>{
+        let items = match &*x.borrow(){
+            Type::Range(..) | Type::List(_) => None,
+            Type::String(text) => Some(text.chars().map(|c| Type::String(c.to_string()).wrap()).collect()),
+            Type::Map(entries) => Some(entries.keys().map(|key| Type::String(key.clone()).wrap()).collect()),
+            x => return Err(RavenError::mismatch("iterate over", &[x])),
+        };
+        Ok(items.map_or(x, |items| Type::List(items).wrap()))
+    }
+
+    /// the `i`th value a `for` loop over the `Iterable` `x` visits, or `None` once it is done.
+    /// lists are read as the loop goes, so elements pushed while looping are visited too
+    pub fn Nth(x: &Object, i: usize) -> RavenResult<Option<Object>>{
         match &*x.borrow(){
-            Type::Bool(x) => {Type::Bool(!*x).wrap()},
-            _ => panic!("attempted to Not {:?}",x),
+            Type::Range(start, end) => {
+                let value = start.checked_add(i as i64).filter(|value| value < end);
+                Ok(value.map(|value| Type::Integer(value).wrap()))
+            },
+            Type::List(items) => Ok(items.get(i).cloned()),
+            x => Err(RavenError::mismatch("iterate over", &[x])),
         }
     }
 
+    pub fn Not(x: Object) -> RavenResult<Object>{
+        match &*x.borrow(){
+            Type::Bool(x) => Ok(Type::Bool(!*x).wrap()),
+            x => Err(RavenError::mismatch("not", &[x])),
+        }
+    }
 
-}
\ No newline at end of file
+
+}
+
+/// checks that `index` names one of `len` elements. floats are accepted when they are whole
+fn position(index: &Type, len: usize) -> RavenResult<usize>{
+    let whole = match index {
+        Type::Integer(index) => *index,
+        Type::Number(index) if index.fract() == 0.0 => *index as i64,
+        index => return Err(RavenError::Runtime(format!("index must be a whole number, found {}", index.toString())))
+    };
+    match usize::try_from(whole) {
+        Ok(at) if at < len => Ok(at),
+        _ => Err(RavenError::Runtime(format!("index {} is out of bounds for length {}", whole, len)))
+    }
+}
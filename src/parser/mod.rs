@@ -1,3 +1,6 @@
 pub mod parser;
 pub mod ast;
-pub mod func;
\ No newline at end of file
+pub mod func;
+pub mod span;
+pub mod error;
+pub mod format;
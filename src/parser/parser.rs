@@ -1,28 +1,30 @@
 use ::std::fs::read_to_string;
-use std::{rc::Rc, cell::RefCell, fmt::Debug};
+use std::{rc::Rc, cell::RefCell, collections::BTreeMap, fmt::Debug};
 
 use crate::interpreter::interpreter::{RefScope, Object, FunctionTypes};
+use crate::interpreter::error::{RavenError, RavenResult};
+use super::{error::SyntaxError, span::Span};
 
+pub type BuiltInFn = dyn Fn(RefScope,Vec<Object>) -> RavenResult<Option<Object>>;
+
+/// a builtin's closure. clones share it, and two of them are equal only when they are the same closure
 #[derive(Clone)]
-pub struct Func(pub &'static dyn Fn(RefScope,Vec<Object>) -> Option<Object>);
+pub struct Func(pub Rc<BuiltInFn>);
 impl Debug for Func{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("Func").field(&(&self.0 as *const _ as usize)).finish()
+        f.debug_tuple("Func").field(&(Rc::as_ptr(&self.0) as *const () as usize)).finish()
     }
 }
 
 impl Func{
-    pub fn new(value: Box<dyn Fn(RefScope,Vec<Object>) -> Option<Object>>) -> Self{
-        let val: &'static dyn Fn(RefScope,Vec<Object>) -> Option<Object> = Box::leak(value);
-        Self(val)
+    pub fn new(value: Box<BuiltInFn>) -> Self{
+        Self(Rc::from(value))
     }
 }
 
 impl PartialEq for Func{
     fn eq(&self, other: &Self) -> bool {
-        let my_ptr = &self.0 as *const _ as usize;
-        let other_ptr = &other.0 as *const _ as usize;
-        my_ptr == other_ptr
+        Rc::ptr_eq(&self.0, &other.0)
     }
 }
 
@@ -32,37 +34,68 @@ fn bbox<T>(val: T) -> Box<T>{
 }
 
 #[inline(always)]
-fn sym(name: &str) -> Type{
-    Type::Symbol(name.to_owned())
+fn bsym(name: &str) -> Box<Type> {
+    Box::new(Type::Symbol(name.to_owned()))
 }
 
 #[inline(always)]
-fn bsym(name: &str) -> Box<Type> {
-    Box::new(Type::Symbol(name.to_owned()))
+fn operator(name: &str, arguments: Vec<Type>, start: usize, end: usize) -> Type {
+    Type::Call { function: bsym(name), arguments, span: Span::new(start, end) }
+}
+
+/// lowers `x++`, `x += y` and friends to `x = name(x, amount)`
+#[inline(always)]
+fn update(variable: Type, name: &str, amount: Type, start: usize, end: usize) -> Type {
+    let value = operator(name, vec![variable.c(), amount], start, end);
+    Type::Assignment { variable: bbox(variable), value: bbox(value), span: Span::new(start, end) }
 }
 
 
+/// one step of an `indexed` chain
+enum Accessor{
+    Index(Type),
+    Member(String),
+    Call(Vec<Type>),
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Type{
     Number(f32),
+    Integer(i64),
     Bool(bool),
     Symbol(String),
+    /// a variable declared in a function or loop body, `index` in the frame `depth` frames up from the
+    /// current one. the parser never makes these, the `Resolver` puts them in place of symbols
+    Local{name: String, depth: usize, index: usize},
     String(String),
-    Call{function: Box<Type>, arguments: Vec<Type>},
-    VariableDeclaration{variable: Box<Type>, value: Box<Type>},
-    Assignment{variable: Box<Type>, value: Box<Type>},
-    CreateFunction{name: Box<Type>, code: Vec<Box<Type>>, parameters: Vec<String>},
+    /// elements are objects of their own, so a list shares them with whoever else holds them
+    List(Vec<Object>),
+    /// string keys kept in sorted order, so printing and `keys` are deterministic
+    Map(BTreeMap<String, Object>),
+    /// the numbers from `start` up to but not including `end`, made by `start..end`
+    Range(i64, i64),
+    Call{function: Box<Type>, arguments: Vec<Type>, span: Span},
+    VariableDeclaration{variable: Box<Type>, value: Box<Type>, span: Span},
+    Assignment{variable: Box<Type>, value: Box<Type>, span: Span},
+    /// the body is shared with every function made from it, so making a closure does not copy it
+    CreateFunction{name: Box<Type>, code: Rc<Vec<Box<Type>>>, parameters: Vec<String>, span: Span},
     Function(FunctionTypes),
-    Conditional{condition: Box<Type>, then: Vec<Type>, otherwise: Option<Vec<Type>>},
-    While{condition: Box<Type>, code: Vec<Type>},
+    Conditional{condition: Box<Type>, then: Vec<Type>, otherwise: Option<Vec<Type>>, span: Span},
+    While{condition: Box<Type>, code: Vec<Type>, span: Span},
+    For{variable: String, iterable: Box<Type>, code: Vec<Type>, span: Span},
+    /// lines run in the scope around them, what `from "m.rv" import f, g` lowers to
     Invocation{code: Vec<Type>},
-    Comment(String)
+    Return{value: Option<Box<Type>>, span: Span},
+    Break{span: Span},
+    Continue{span: Span},
+    Comment(String),
+    /// an empty line between two statements. it does nothing, it is kept so `raven fmt` can put it back
+    Blank
 }
 
-impl Into<Rc<RefCell<Self>>> for Type{
-    fn into(self) -> Rc<RefCell<Self>> {
-        Rc::new(RefCell::new(self))
+impl From<Type> for Rc<RefCell<Type>>{
+    fn from(value: Type) -> Self {
+        Rc::new(RefCell::new(value))
     }
 }
 
@@ -73,13 +106,62 @@ impl Type {
     pub fn toString(&self) -> String{
         match self {
             Type::Number(e) =>    format!("{}", e),
-            Type::Symbol(e) => format!("{}", e),
-            Type::String(e) => format!("{}", e),
+            Type::Integer(e) => format!("{}", e),
+            Type::Symbol(e) | Type::Local { name: e, .. } => e.to_string(),
+            Type::String(e) => e.to_string(),
             Type::Bool(e) => format!("{}", e),
+            Type::List(items) => {
+                let items = items.iter().map(|item| item.borrow().quoted()).collect::<Vec<_>>();
+                format!("[{}]", items.join(", "))
+            },
+            Type::Map(entries) => {
+                let entries = entries.iter().map(|(key, value)| format!("\"{}\": {}", key, value.borrow().quoted())).collect::<Vec<_>>();
+                format!("{{{}}}", entries.join(", "))
+            },
+            Type::Range(start, end) => format!("{}..{}", start, end),
             _ => format!("{:?}", self)
         }
     }
 
+    /// like `toString`, but strings keep their quotes so they stand out inside lists and maps
+    pub fn quoted(&self) -> String{
+        match self {
+            Type::String(e) => format!("\"{}\"", e),
+            other => other.toString()
+        }
+    }
+
+    /// where this node was parsed from, if it is a node that keeps one
+    pub fn span(&self) -> Option<Span>{
+        match self {
+            Type::Call { span, .. } |
+            Type::VariableDeclaration { span, .. } |
+            Type::Assignment { span, .. } |
+            Type::CreateFunction { span, .. } |
+            Type::Conditional { span, .. } |
+            Type::While { span, .. } |
+            Type::For { span, .. } |
+            Type::Return { span, .. } |
+            Type::Break { span } |
+            Type::Continue { span } => Some(*span),
+            _ => None
+        }
+    }
+
+    pub fn typeName(&self) -> &'static str{
+        match self {
+            Type::Number(_) => "number",
+            Type::Integer(_) => "integer",
+            Type::Bool(_) => "bool",
+            Type::String(_) => "string",
+            Type::List(_) => "list",
+            Type::Map(_) => "map",
+            Type::Range(..) => "range",
+            Type::Function(_) => "function",
+            _ => "expression"
+        }
+    }
+
     #[inline(always)]
     pub fn c(&self) -> Type{
         self.clone()
@@ -89,7 +171,7 @@ impl Type {
 
 
 peg::parser!{
-    pub grammar RavenParser() for str {
+    pub grammar RavenParser(source: &str) for str {
         rule whitespace()
         = [' '| '\t' | '\n' | '\r' |'\u{A}']
         rule _ 
@@ -98,19 +180,26 @@ peg::parser!{
         = whitespace()+
 
         rule number() -> Type
-        = n:$(['0'..='9' | '.' | '-']+) { Type::Number(n.parse::<f32>().unwrap_or_else(|_|panic!("value: {} is not a valid number!", n)))}
+        = n:$((['0'..='9' | '-'] / "." !".")+) {?
+            // a literal without a decimal point is an exact integer, `1.0` is a float
+            if n.contains('.') {
+                n.parse::<f32>().map(Type::Number).or(Err("number"))
+            } else {
+                n.parse::<i64>().map(Type::Integer).or(Err("number"))
+            }
+        }
 
 
         rule Arithmetic() -> Type
         = precedence!{
-            x:(@) _ "+" _  y:@ { Type::Call{function: bsym("__add__"), arguments: vec![x,y] } }
-            x:(@) _ "-" _  y:@ { Type::Call{function: bsym("__sub__"), arguments: vec![x,y] } }
+            x:(@) _ s:position!() "+" e:position!() _ y:@ { operator("__add__", vec![x,y], s, e) }
+            x:(@) _ s:position!() "-" e:position!() _ y:@ { operator("__sub__", vec![x,y], s, e) }
             --
-            x:(@) _ "*" _  y:@ { Type::Call{function: bsym("__mul__"), arguments: vec![x,y] } }
-            x:(@) _ "/" _  y:@ { Type::Call{function: bsym("__div__"), arguments: vec![x,y] } }
+            x:(@) _ s:position!() "*" e:position!() _ y:@ { operator("__mul__", vec![x,y], s, e) }
+            x:(@) _ s:position!() "/" e:position!() _ y:@ { operator("__div__", vec![x,y], s, e) }
             --
-            x:(@) _ "**" _  y:@ { Type::Call{function: bsym("__pow__"), arguments: vec![x,y] } }
-            x:(@) _ "%" _  y:@ { Type::Call{function: bsym("__mod__"), arguments: vec![x,y] } }
+            x:(@) _ s:position!() "**" e:position!() _ y:@ { operator("__pow__", vec![x,y], s, e) }
+            x:(@) _ s:position!() "%" e:position!() _ y:@ { operator("__mod__", vec![x,y], s, e) }
             --
             x:Atom() {x}
             "(" _ x:Arithmetic() _ ")" _ {x}
@@ -119,28 +208,30 @@ peg::parser!{
 
         rule Operation() -> Type
         = precedence!{
-            x:symbol() _ "++" _ { Type::Assignment { variable: bbox(x.c()), value: bbox(Type::Call{function: bsym("__add__"), arguments: vec![x, Type::Number(1.0)] }) } }
-            x:symbol() _ "--" _ { Type::Assignment { variable: bbox(x.c()), value: bbox(Type::Call{function: bsym("__sub__"), arguments: vec![x, Type::Number(1.0)] }) } }
-            x:symbol() _ "+=" _  y:@ { Type::Assignment { variable: bbox(x.c()), value: bbox(Type::Call{function: bsym("__add__"), arguments: vec![x,y] }) } }
-            x:symbol() _ "-=" _  y:@ { Type::Assignment { variable: bbox(x.c()), value: bbox(Type::Call{function: bsym("__sub__"), arguments: vec![x,y] }) } }
-            x:symbol() _ "*=" _  y:@ { Type::Assignment { variable: bbox(x.c()), value: bbox(Type::Call{function: bsym("__mul__"), arguments: vec![x,y] }) } }
-            x:symbol() _ "/=" _  y:@ { Type::Assignment { variable: bbox(x.c()), value: bbox(Type::Call{function: bsym("__div__"), arguments: vec![x,y] }) } }
-            x:symbol() _ "**=" _  y:@ { Type::Assignment { variable: bbox(x.c()), value: bbox(Type::Call{function: bsym("__pow__"), arguments: vec![x,y] }) } }
-            x:symbol() _ "%=" _  y:@ { Type::Assignment { variable: bbox(x.c()), value: bbox(Type::Call{function: bsym("__mod__"), arguments: vec![x,y] }) } }
+            s:position!() x:symbol() _ "++" e:position!() _ { update(x, "__add__", Type::Integer(1), s, e) }
+            s:position!() x:symbol() _ "--" e:position!() _ { update(x, "__sub__", Type::Integer(1), s, e) }
+            s:position!() x:symbol() _ "+=" e:position!() _ y:@ { update(x, "__add__", y, s, e) }
+            s:position!() x:symbol() _ "-=" e:position!() _ y:@ { update(x, "__sub__", y, s, e) }
+            s:position!() x:symbol() _ "*=" e:position!() _ y:@ { update(x, "__mul__", y, s, e) }
+            s:position!() x:symbol() _ "/=" e:position!() _ y:@ { update(x, "__div__", y, s, e) }
+            s:position!() x:symbol() _ "**=" e:position!() _ y:@ { update(x, "__pow__", y, s, e) }
+            s:position!() x:symbol() _ "%=" e:position!() _ y:@ { update(x, "__mod__", y, s, e) }
             --
-            x:(@) _ "&&" _ y:@ { Type::Call{function: bsym("__and__"), arguments: vec![x,y] } }
-            x:(@) _ "||" _ y:@ { Type::Call{function: bsym("__or__"), arguments: vec![x,y] } }
+            x:(@) _ s:position!() "&&" e:position!() _ y:@ { operator("__and__", vec![x,y], s, e) }
+            x:(@) _ s:position!() "||" e:position!() _ y:@ { operator("__or__", vec![x,y], s, e) }
             --
-            x:(@) _ "==" _ y:@ { Type::Call{function: bsym("__eq__"), arguments: vec![x,y] } }
-            x:(@) _ "!=" _ y:@ { Type::Call{function: bsym("__ne__"), arguments: vec![x,y] } }
+            x:(@) _ s:position!() "==" e:position!() _ y:@ { operator("__eq__", vec![x,y], s, e) }
+            x:(@) _ s:position!() "!=" e:position!() _ y:@ { operator("__ne__", vec![x,y], s, e) }
             --
-            x:(@) _ "<=" _ y:@ { Type::Call{function: bsym("__le__"), arguments: vec![x,y] } }
-            x:(@) _ ">=" _ y:@ { Type::Call{function: bsym("__ge__"), arguments: vec![x,y] } }
+            x:(@) _ s:position!() "<=" e:position!() _ y:@ { operator("__le__", vec![x,y], s, e) }
+            x:(@) _ s:position!() ">=" e:position!() _ y:@ { operator("__ge__", vec![x,y], s, e) }
             --
-            x:(@) _ "<" _ y:@ { Type::Call{function: bsym("__lt__"), arguments: vec![x,y] } }
-            x:(@) _ ">" _ y:@ { Type::Call{function: bsym("__gt__"), arguments: vec![x,y] } }
+            x:(@) _ s:position!() "<" e:position!() _ y:@ { operator("__lt__", vec![x,y], s, e) }
+            x:(@) _ s:position!() ">" e:position!() _ y:@ { operator("__gt__", vec![x,y], s, e) }
             --
-            "!" _ x:(@) { Type::Call{function: bsym("__not__"), arguments: vec![x] } }
+            x:(@) _ s:position!() ".." e:position!() _ y:@ { operator("__range__", vec![x,y], s, e) }
+            --
+            s:position!() "!" e:position!() _ x:(@) { operator("__not__", vec![x], s, e) }
             --
             x:Arithmetic() {x}
             "(" _ x:Operation() _ ")" _ {x}       
@@ -148,7 +239,7 @@ peg::parser!{
 
 
         rule symbol() -> Type
-        = n:$(['A'..='z'](['0'..='9'] / ['A'..='z'])*) { Type::Symbol(n.to_string()) }
+        = n:$(['A'..='Z' | 'a'..='z' | '_'] ['A'..='Z' | 'a'..='z' | '_' | '0'..='9']*) { Type::Symbol(n.to_string()) }
         
         rule spaced_symbol() -> Type
         = _ n:symbol() _ {n}
@@ -156,41 +247,74 @@ peg::parser!{
         rule string() -> Type
         = "\"" n:$([^ '"']*) "\"" { Type::String(n.to_string())}
     
+        #[cache]
         rule call() -> Type
-        = _ sym:symbol() _ "(" expr:(parse() ** ",") ")"  &_  {Type::Call{function: bbox(sym), arguments: expr}}
+        = _ s:position!() sym:symbol() _ "(" expr:(parse() ** ",") ")" e:position!() &_  {Type::Call{function: bbox(sym), arguments: expr, span: Span::new(s, e)}}
+
+        rule list() -> Type
+        = _ s:position!() "[" _ items:(parse() ** (_ "," _)) _ ("," _)? "]" e:position!() { operator("__list__", items, s, e) }
+
+        // `{ "k": v }` lowers to `__map__("k", v)`. blocks only ever follow `if`, `while` and
+        // function headers, where `bracket_block` is asked for explicitly, so the two never compete
+        rule map() -> Type
+        = _ s:position!() "{" _ entries:(map_entry() ** (_ "," _)) _ ("," _)? "}" e:position!() {
+            operator("__map__", entries.into_iter().flat_map(|(key, value)| [key, value]).collect(), s, e)
+        }
+        rule map_entry() -> (Type, Type)
+        = key:parse() _ ":" _ value:parse() { (key, value) }
+
+        // `xs[i][j]` lowers to `__index__(__index__(xs, i), j)` and `m.f` to `__member__(m, "f")`.
+        // whatever the chain ends up at can be called right away, as in `m.f(x)` or `fs[0](x)`
+        rule indexed() -> Type
+        = _ s:position!() target:(call() / list() / map() / symbol() / string()) accessors:(accessor()+) {
+            accessors.into_iter().fold(target, |target, (accessor, e)| match accessor {
+                Accessor::Index(index) => operator("__index__", vec![target, index], s, e),
+                Accessor::Member(name) => operator("__member__", vec![target, Type::String(name)], s, e),
+                Accessor::Call(arguments) => Type::Call { function: bbox(target), arguments, span: Span::new(s, e) },
+            })
+        }
+        rule accessor() -> (Accessor, usize)
+        = accessor:("[" _ i:parse() _ "]" {Accessor::Index(i)} / "." name:symbol() {Accessor::Member(name.toString())} / "(" arguments:(parse() ** ",") ")" {Accessor::Call(arguments)}) e:position!() { (accessor, e) }
 
         rule chain_call() -> Type
-        = _ "$" _ sym:symbol() _ expr:(Operation() ** " ")  _  {Type::Call{function: bbox(sym), arguments: expr}}
+        = _ s:position!() "$" _ sym:symbol() _ expr:(Operation() ** " ") e:position!() _  {Type::Call{function: bbox(sym), arguments: expr, span: Span::new(s, e)}}
 
         rule comment() -> Type
-        = _ "//" n:$([^ '\n']*) "\n"? {Type::Comment(n.to_string())}
+        = _ "//" n:$([^ '\n']*) {Type::Comment(n.to_string())}
 
         rule pipe_right() -> Type
-        = _ start:(start:Operation() _ "|>" _ {start})? _ expr:(pipe_call_right() ++ "|>") _  {
+        = _ start:(start:Operation() _ "|>" _ {start})? _ expr:(pipe_call_right() ++ "|>") _  {?
+            if expr.len() == 1 && start.is_none() {
+                return Err("pipe")
+            }
             let mut last = start;
             for func in expr {
-                if let Type::Call { function, mut arguments } = func {
+                if let Type::Call { function, mut arguments, span } = func {
                     if let Some(last_func) = last {
                         arguments.insert(0, last_func);
                     }
-                    last = Some(Type::Call { function, arguments});                   
+                    last = Some(Type::Call { function, arguments, span });
                 }
             }
-            return last.unwrap()
+            Ok(last.unwrap())
         }
 
         rule pipe_left() -> Type
-        = _ expr:(pipe_call_left() ++ "<|") _ end:("<|" _ end:Operation() _ {end})? _ {
+        = _ expr:(pipe_call_left() ++ "<|") _ end:("<|" _ end:Operation() _ {end})? _ {?
+            // a lone call is not a pipe, leave it to the rules that can continue it with `+` or `[i]`
+            if expr.len() == 1 && end.is_none() {
+                return Err("pipe")
+            }
             let mut last = end;
             for func in expr.into_iter().rev() {
-                if let Type::Call { function, mut arguments } = func {
+                if let Type::Call { function, mut arguments, span } = func {
                     if let Some(last_func) = last {
                         arguments.push(last_func);
                     }
-                    last = Some(Type::Call { function, arguments});                   
+                    last = Some(Type::Call { function, arguments, span });
                 }
             }
-            return last.unwrap()
+            Ok(last.unwrap())
         }
 
         rule Else() -> Vec<Type>
@@ -199,52 +323,100 @@ peg::parser!{
         = code: if_condition() {vec![code]}
         rule else_elif() -> Vec<Type>
         = "else" _ res:(Else() / Elif()) {res}
+        #[cache]
         rule if_condition() -> Type
-        = _ "if" _ "("? _ condition:Operation() _ ")"? _ then:bracket_block() _ otherwise:(else_elif())? _ {
-            Type::Conditional{condition: bbox(condition), then, otherwise}
+        = _ s:position!() "if" _ "("? _ condition:Operation() e:position!() _ ")"? _ then:bracket_block() _ otherwise:(else_elif())? _ {
+            Type::Conditional{condition: bbox(condition), then, otherwise, span: Span::new(s, e)}
         }
 
+        #[cache]
         rule while_loop() -> Type
-        = _ "while" _ "("? _ condition:Operation() _ ")"? _ code:bracket_block() _ {
-            Type::While{condition: bbox(condition), code}
+        = _ s:position!() "while" _ "("? _ condition:Operation() e:position!() _ ")"? _ code:bracket_block() _ {
+            Type::While{condition: bbox(condition), code, span: Span::new(s, e)}
+        }
+
+        #[cache]
+        rule for_loop() -> Type
+        = _ s:position!() "for" __ variable:symbol() __ "in" __ iterable:Operation() e:position!() _ code:bracket_block() _ {
+            Type::For{variable: variable.toString(), iterable: bbox(iterable), code, span: Span::new(s, e)}
         }
 
         rule function() -> Type
-        = _ "fn" _ name:symbol() _ "(" _ parameters:(spaced_symbol() ** ",") _ ")" _ code:bracket_block() _ {
+        = _ s:position!() "fn" _ name:symbol() _ "(" _ parameters:(spaced_symbol() ** ",") _ ")" e:position!() _ code:bracket_block() _ {
             let name = bbox(name);
-            let code = code.into_iter().map(Box::new).collect();
+            let code = Rc::new(code.into_iter().map(Box::new).collect());
             let parameters = parameters.into_iter().map(|e| e.toString()).collect();
-            Type::CreateFunction { name, code, parameters }
+            Type::CreateFunction { name, code, parameters, span: Span::new(s, e) }
         }
 
         rule lambda() -> Type
-        = _ "(" _ parameters:(spaced_symbol() ** ",") _ ")" _ "=>" _ code:bracket_block() _ {
+        = _ s:position!() "(" _ parameters:(spaced_symbol() ** ",") _ ")" _ "=>" e:position!() _ code:bracket_block() _ {
             let parameters = parameters.into_iter().map(|e| e.toString()).collect();
-            let code = code.into_iter().map(Box::new).collect();
-            Type::CreateFunction { name: bsym(""), parameters, code }
+            let code = Rc::new(code.into_iter().map(Box::new).collect());
+            Type::CreateFunction { name: bsym(""), parameters, code, span: Span::new(s, e) }
+        }
+
+        rule return_statement() -> Type
+        = _ s:position!() "return" !['A'..='Z' | 'a'..='z' | '_' | '0'..='9'] e:position!() value:([' ' | '\t']* !['\n' | '\r' | ';' | '}'] v:parse() {v})? _ {
+            Type::Return { value: value.map(Box::new), span: Span::new(s, e) }
+        }
+
+        rule loop_control() -> Type
+        = _ s:position!() keyword:$("break" / "continue") !['A'..='Z' | 'a'..='z' | '_' | '0'..='9'] e:position!() _ {
+            let span = Span::new(s, e);
+            if keyword == "break" { Type::Break { span } } else { Type::Continue { span } }
         }
 
         rule assignment() -> Type
-        = _ name:symbol() _ "=" _ expr:parse() _ {
-            Type::Assignment { variable: bbox(name), value: bbox(expr) }
+        = _ s:position!() name:symbol() _ "=" _ expr:parse() e:position!() _ {
+            Type::Assignment { variable: bbox(name), value: bbox(expr), span: Span::new(s, e) }
+        }
+        rule index_assignment() -> Type
+        = _ s:position!() target:indexed() _ "=" _ expr:parse() e:position!() _ {?
+            match target {
+                Type::Call { function, mut arguments, .. } if *function == Type::Symbol("__index__".to_string()) => {
+                    arguments.push(expr);
+                    Ok(operator("__setindex__", arguments, s, e))
+                },
+                _ => Err("index")
+            }
+        }
+        // `import "m.rv" as m` lowers to `let m = __import__("m.rv")`
+        rule import() -> Type
+        = _ s:position!() "import" __ path:string() __ "as" __ name:symbol() e:position!() _ {
+            let span = Span::new(s, e);
+            Type::VariableDeclaration { variable: bbox(name), value: bbox(operator("__import__", vec![path], s, e)), span }
+        }
+        // `from "m.rv" import f, g` lowers to `let f = __import__("m.rv", "f")` and the same for `g`
+        rule from_import() -> Type
+        = _ s:position!() "from" __ path:string() __ "import" __ names:(symbol() ++ (_ "," _)) e:position!() _ {
+            let code = names.into_iter().map(|name| {
+                let value = operator("__import__", vec![path.c(), Type::String(name.toString())], s, e);
+                Type::VariableDeclaration { variable: bbox(name), value: bbox(value), span: Span::new(s, e) }
+            }).collect();
+            Type::Invocation { code }
         }
         rule declaration() -> Type
-        = _ "let" _ name:symbol() _ "=" _ expr:parse() _ {
-            Type::VariableDeclaration { variable: bbox(name), value: bbox(expr) }
+        = _ s:position!() "let" _ name:symbol() _ "=" _ expr:parse() e:position!() _ {
+            Type::VariableDeclaration { variable: bbox(name), value: bbox(expr), span: Span::new(s, e) }
         }
 
 
         rule Atom() -> Type = precedence!{
             n:chain_call() {n}
             --
+            n:indexed() {n}
             n:call()   {n}
             --
             n:while_loop() {n}
+            n:for_loop() {n}
             n:if_condition() {n}
             --
             n:number() {n}
             n:symbol() {n}
             n:string() {n}
+            n:list() {n}
+            n:map() {n}
             --
             _ "(" _ e:Atom() _ ")" _ { e }
         }
@@ -252,8 +424,14 @@ peg::parser!{
         rule parse_intermediate() -> Type = precedence!{
             n:comment() {n}
             --
+            n:return_statement() {n}
+            n:loop_control() {n}
+            --
             n:declaration() {n}
+            n:import() {n}
+            n:from_import() {n}
             --
+            n:index_assignment() {n}
             n:assignment() {n}
             --
             // n:dual_pipe() {n}
@@ -267,16 +445,20 @@ peg::parser!{
             --
             n:if_condition() {n}
             n:while_loop() {n}
+            n:for_loop() {n}
             --
             // n:Arithmetic() {n}
             // --
             n:Operation() {n}
             --
+            n:indexed() {n}
             n:call()   {n}
             --
             n:number() {n}
             n:symbol() {n}
             n:string() {n}
+            n:list() {n}
+            n:map() {n}
         }
 
         rule parse() -> Type = 
@@ -302,16 +484,34 @@ peg::parser!{
         = "{" _ code:parseBlock() _ "}" {code}
     
         rule parseBlock() -> Vec<Type> =
-            _ code:((x:parse() (";"/"\n"/_) {x})*) _ {code}
+            _ code:((s:position!() x:parse() (";"/"\n"/_) {(s, x)})*) _ {
+                let mut block = vec![];
+                for (start, line) in code {
+                    if !block.is_empty() && blankBefore(source, start) {
+                        block.push(Type::Blank);
+                    }
+                    block.push(line);
+                }
+                block
+            }
 
         pub rule ParseFile() -> Vec<Type> =
             code:parseBlock() {code}       
     }
 }
 
-pub fn ParseFile(file: &str) -> Vec<Type>{
-    RavenParser::ParseFile(&read_to_string(file).unwrap()).unwrap()
+pub fn ParseFile(file: &str) -> RavenResult<Vec<Type>>{
+    let code = read_to_string(file).map_err(|error| RavenError::Io(format!("cannot read {}: {}", file, error)))?;
+    ParseString(&code)
 }
-pub fn ParseString(code: &str) -> Vec<Type>{
-    RavenParser::ParseFile(code).unwrap()
+/// whether the whitespace around `at`, where a statement is about to be parsed, holds an empty line
+fn blankBefore(source: &str, at: usize) -> bool{
+    let whitespace = |c: char| c.is_ascii_whitespace();
+    let start = source.len() - source[at..].trim_start_matches(whitespace).len();
+    let gap = &source[source[..start].trim_end_matches(whitespace).len()..start];
+    gap.matches('\n').count() > 1
+}
+
+pub fn ParseString(code: &str) -> RavenResult<Vec<Type>>{
+    RavenParser::ParseFile(code, code).map_err(|error| RavenError::Syntax(SyntaxError::new(code, error)))
 }
\ No newline at end of file
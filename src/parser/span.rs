@@ -0,0 +1,58 @@
+use std::fmt::Debug;
+
+use crate::color::Colorize;
+
+/// byte range of a node in the source it was parsed from.
+/// spans never take part in equality, so the same code compares equal wherever it was written
+#[derive(Clone, Copy, Default)]
+pub struct Span{
+    pub start: usize,
+    pub end: usize
+}
+
+impl PartialEq for Span{
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Debug for Span{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+impl Span{
+    pub fn new(start: usize, end: usize) -> Span{
+        Span { start, end }
+    }
+
+    /// 1-based line and column of `offset`, counting columns in characters
+    pub fn lineCol(source: &str, offset: usize) -> (usize, usize){
+        let offset = offset.min(source.len());
+        let before = &source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        (line, column)
+    }
+
+    /// rustc-style snippet pointing at the first line of the span
+    pub fn render(&self, source: &str, file: &str, message: &str) -> String{
+        let (line, column) = Span::lineCol(source, self.start);
+        let text = source.lines().nth(line - 1).unwrap_or("");
+        let start = self.start.min(source.len());
+        let underlined = source[start..self.end.clamp(start, source.len())].trim_end();
+        let underlined = underlined.lines().next().unwrap_or("").chars().count().max(1);
+
+        let gutter = " ".repeat(line.to_string().len());
+        let bar = "|".blue().bold();
+        format!(
+            "{}: {}\n{}{} {}:{}:{}\n{} {}\n{} {} {}\n{} {} {}{}",
+            "error".red().bold(), message.bold(),
+            gutter, "-->".blue().bold(), file, line, column,
+            gutter, bar,
+            line.to_string().blue().bold(), bar, text,
+            gutter, bar, " ".repeat(column - 1), "^".repeat(underlined).red().bold()
+        )
+    }
+}
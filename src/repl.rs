@@ -0,0 +1,138 @@
+use crate::color::Colorize;
+use rustyline::{DefaultEditor, error::ReadlineError};
+
+use crate::interpreter::{interpreter::Interpreter, error::RavenError};
+use crate::parser::parser::{ParseString, Type};
+
+const HELP: &str = "\
+:ast <code>    show the tree `code` parses into
+:vars          list the variables declared so far
+:reset         forget every variable
+:help          show this message
+:quit          leave, as does ctrl-d";
+
+pub enum Reply{
+    /// the input so far is the start of something longer, keep reading
+    Incomplete,
+    Output(String),
+    Quit,
+}
+
+/// the part of the repl that does not touch the terminal: it collects lines until they
+/// parse, runs them in a global scope that lives as long as the session, and says what to print
+pub struct Repl{
+    interpreter: Interpreter,
+    pending: String,
+    vm: bool,
+}
+
+impl Repl{
+    pub fn new(vm: bool) -> Repl{
+        let interpreter = Interpreter::with_stdlib();
+        Repl { interpreter, pending: String::new(), vm }
+    }
+
+    pub fn prompt(&self) -> &'static str{
+        if self.pending.is_empty() { ">> " } else { ".. " }
+    }
+
+    /// drops a half typed statement
+    pub fn cancel(&mut self){
+        self.pending.clear();
+    }
+
+    pub fn line(&mut self, line: &str) -> Reply{
+        if self.pending.is_empty() {
+            if let Some(command) = line.trim().strip_prefix(':') {
+                return self.command(command)
+            }
+        }
+
+        // an empty line gives up on waiting for the rest and reports what is wrong instead
+        let forced = line.trim().is_empty() && !self.pending.is_empty();
+        self.pending.push_str(line);
+        self.pending.push('\n');
+        // naming a function is a statement, there is no need to echo it back
+        let quiet = match ParseString(&self.pending) {
+            Err(RavenError::Syntax(error)) if !forced && error.span.start >= self.pending.trim_end().len() => return Reply::Incomplete,
+            Ok(nodes) => matches!(nodes.last(), Some(Type::CreateFunction { name, .. }) if name.toString() != ""),
+            Err(_) => false,
+        };
+
+        let code = std::mem::take(&mut self.pending);
+        #[cfg(feature = "vm")]
+        let result = if self.vm {
+            self.interpreter.runVM(code.clone(), false)
+        } else {
+            self.interpreter.run(code.clone(), false)
+        };
+        #[cfg(not(feature = "vm"))]
+        let result = self.interpreter.run(code.clone(), false);
+        match result {
+            Ok(Some(value)) if !quiet => Reply::Output(value.borrow().to_string(0, 0)),
+            Ok(_) => Reply::Output(String::new()),
+            Err(error) => Reply::Output(error.render(&code, "<repl>")),
+        }
+    }
+
+    fn command(&mut self, command: &str) -> Reply{
+        let (name, rest) = command.split_once(' ').unwrap_or((command, ""));
+        let output = match name {
+            "ast" => match ParseString(rest) {
+                Ok(nodes) => nodes.iter().map(|node| node.to_string(0, 0)).collect::<Vec<_>>().join("\n"),
+                Err(error) => error.render(rest, "<repl>"),
+            },
+            "vars" => self.interpreter.globals().into_iter()
+                .map(|(name, value)| format!("{} = {}", name.red(), value.borrow().to_string(0, 0)))
+                .collect::<Vec<_>>().join("\n"),
+            "reset" => {
+                *self = Repl::new(self.vm);
+                String::new()
+            },
+            "help" => HELP.to_string(),
+            "quit" | "q" => return Reply::Quit,
+            other => format!("{}: unknown command `:{}`, try `:help`", "error".red().bold(), other),
+        };
+        Reply::Output(output)
+    }
+}
+
+fn historyFile() -> Option<std::path::PathBuf>{
+    std::env::var_os("HOME").map(|home| std::path::Path::new(&home).join(".raven_history"))
+}
+
+/// reads lines from the terminal until `:quit` or ctrl-d, keeping history between sessions
+pub fn start(vm: bool) -> rustyline::Result<()>{
+    let mut editor = DefaultEditor::new()?;
+    let history = historyFile();
+    if let Some(history) = &history {
+        // there is no history the first time around
+        let _ = editor.load_history(history);
+    }
+
+    println!("raven repl, `:help` for commands");
+    let mut repl = Repl::new(vm);
+    loop {
+        match editor.readline(repl.prompt()) {
+            Ok(line) => {
+                if !line.trim().is_empty() {
+                    editor.add_history_entry(line.as_str())?;
+                }
+                match repl.line(&line) {
+                    Reply::Incomplete => {},
+                    Reply::Output(output) if output.is_empty() => {},
+                    Reply::Output(output) => println!("{}", output),
+                    Reply::Quit => break,
+                }
+            },
+            Err(ReadlineError::Interrupted) => repl.cancel(),
+            Err(ReadlineError::Eof) => break,
+            Err(error) => return Err(error),
+        }
+    }
+
+    if let Some(history) = &history {
+        editor.save_history(history)?;
+    }
+    Ok(())
+}
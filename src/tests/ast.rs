@@ -1,27 +1,29 @@
 #![allow(unused_imports)]
 use crate::parser::parser::ParseString;
 use crate::parser::parser::Type;
+use crate::parser::span::Span;
+use std::rc::Rc;
 
 #[test]
 pub fn basic(){
     const CODE: &str = "10";
-    let ast = ParseString(CODE);
-    assert_eq!(ast, vec![Type::Number(10.0)])
+    let ast = ParseString(CODE).unwrap();
+    assert_eq!(ast, vec![Type::Integer(10)])
 }
 
 #[test]
 pub fn function_call(){
     println!("beginning first test");
     const CODE: &str     = r##"print("hello")"##;
-    let ast     = ParseString(CODE);
+    let ast     = ParseString(CODE).unwrap();
     let print   = Box::new(Type::Symbol("print".to_owned()));
     let args    = vec![Type::String("hello".to_owned())];
-    let expected       = vec![Type::Call{function: print,arguments: args}];
+    let expected       = vec![Type::Call{function: print,arguments: args, span: Span::default()}];
     assert_eq!(ast, expected);
 
     println!("beginning second test");
     const CODE2: &str     = r##"print("hello");"##;
-    let ast = ParseString(CODE2);
+    let ast = ParseString(CODE2).unwrap();
     assert_eq!(ast, expected);
 
 }
@@ -29,18 +31,18 @@ pub fn function_call(){
 #[test]
 pub fn function_nested(){
     const CODE: &str     = r##"print(anchor(10,"hello", pop()))"##;
-    let ast     = ParseString(CODE);
+    let ast     = ParseString(CODE).unwrap();
 
     let print   = Box::new(Type::Symbol("print".to_owned()));
     let anchor  = Box::new(Type::Symbol("anchor".to_owned()));
     let pop     = Box::new(Type::Symbol("pop".to_owned()));
     let hello       = Type::String("hello".to_owned());
-    let ten         = Type::Number(10.0);
+    let ten         = Type::Integer(10);
     
 
-    let arg_0   = vec![ten, hello, Type::Call{function: pop, arguments: vec![]}];
-    let arg_1   = vec![Type::Call{function: anchor, arguments: arg_0}];
-    let expected= vec![Type::Call{function: print, arguments: arg_1}];
+    let arg_0   = vec![ten, hello, Type::Call{function: pop, arguments: vec![], span: Span::default()}];
+    let arg_1   = vec![Type::Call{function: anchor, arguments: arg_0, span: Span::default()}];
+    let expected= vec![Type::Call{function: print, arguments: arg_1, span: Span::default()}];
     assert_eq!(ast, expected);
 }
 
@@ -50,9 +52,9 @@ pub fn function(){
 
     }"##;
 
-    let ast     = ParseString(CODE);
+    let ast     = ParseString(CODE).unwrap();
     let print   = Box::new(Type::Symbol("print".to_owned()));
-    let function         = Type::CreateFunction { name: print, code: vec![], parameters: vec![] };
+    let function         = Type::CreateFunction { name: print, code: Rc::new(vec![]), parameters: vec![], span: Span::default() };
     let expected       = vec![function];
     assert_eq!(ast, expected);
 
@@ -62,18 +64,18 @@ pub fn function(){
         print("hi")
     }"##;
 
-    let ast     = ParseString(CODE2);
+    let ast     = ParseString(CODE2).unwrap();
     let print   = Box::new(Type::Symbol("x".to_owned()));
 
     let k = Box::new(Type::Symbol("k".to_owned()));
-    let declaration = Type::VariableDeclaration { variable: k.clone(), value:  Box::new(Type::Number(20.0)) };
-    let assignment = Type::Assignment { variable: k, value: Box::new(Type::Number(2.0)) };
-    let functionCall = Type::Call { function: Box::new(Type::Symbol("print".to_owned())), arguments: vec![Type::String("hi".to_owned())] };
+    let declaration = Type::VariableDeclaration { variable: k.clone(), value:  Box::new(Type::Integer(20)), span: Span::default() };
+    let assignment = Type::Assignment { variable: k, value: Box::new(Type::Integer(2)), span: Span::default() };
+    let functionCall = Type::Call { function: Box::new(Type::Symbol("print".to_owned())), arguments: vec![Type::String("hi".to_owned())], span: Span::default() };
 
     let code = {vec![declaration, assignment, functionCall]}
     .into_iter()
     .map(Box::new).collect();
-    let function         = Type::CreateFunction { name: print, code, parameters: vec![] };
+    let function         = Type::CreateFunction { name: print, code: Rc::new(code), parameters: vec![], span: Span::default() };
     let expected       = vec![function];
     assert_eq!(ast, expected);
 }
@@ -82,9 +84,9 @@ pub fn function(){
 #[test]
 pub fn assignment(){
     const BASIC: &str     = r##"y = 20;"##;
-    let ast     = ParseString(BASIC);
+    let ast     = ParseString(BASIC).unwrap();
     let variable   = Type::Symbol("y".to_owned()).into();
-    let function         = Type::Assignment { variable, value: Type::Number(20.0).into() };
+    let function         = Type::Assignment { variable, value: Type::Integer(20).into(), span: Span::default() };
     let expected       = vec![function];
     assert_eq!(ast, expected);
 }
@@ -92,9 +94,9 @@ pub fn assignment(){
 #[test]
 pub fn declaration(){
     const BASIC: &str     = r##"let y = 20;"##;
-    let ast     = ParseString(BASIC);
+    let ast     = ParseString(BASIC).unwrap();
     let variable   = Type::Symbol("y".to_owned()).into();
-    let function         = Type::VariableDeclaration { variable, value: Type::Number(20.0).into() };
+    let function         = Type::VariableDeclaration { variable, value: Type::Integer(20).into(), span: Span::default() };
     let expected       = vec![function];
     assert_eq!(ast, expected);
 }
@@ -106,18 +108,18 @@ pub fn declaration_and_assignment(){
     let x = "hello";
     x = "hi";
 "##;
-    let ast     = ParseString(BASIC);
+    let ast     = ParseString(BASIC).unwrap();
     let y   = Type::Symbol("y".to_owned()).into();
     let x   = Type::Symbol("x".to_owned()).into();
     let mut expected       = vec![];
 
-    expected.push(Type::VariableDeclaration { variable: y, value: Type::Number(20.0).into() });
+    expected.push(Type::VariableDeclaration { variable: y, value: Type::Integer(20).into(), span: Span::default() });
     let y   = Type::Symbol("y".to_owned()).into();
-    expected.push(Type::Assignment { variable: y, value: Type::Number(10.0).into() });
+    expected.push(Type::Assignment { variable: y, value: Type::Integer(10).into(), span: Span::default() });
 
-    expected.push(Type::VariableDeclaration { variable: x, value: Type::String("hello".into()).into() });
+    expected.push(Type::VariableDeclaration { variable: x, value: Type::String("hello".into()).into(), span: Span::default() });
     let x   = Type::Symbol("x".to_owned()).into();
-    expected.push(Type::Assignment { variable: x, value: Type::String("hi".into()).into() });
+    expected.push(Type::Assignment { variable: x, value: Type::String("hi".into()).into(), span: Span::default() });
     
     assert_eq!(ast, expected);
 }
@@ -127,19 +129,19 @@ pub fn functional(){
 
     // println!("beginning first test");
     const BASIC: &str     = r##"$ test 10 "hello""##;
-    let ast     = ParseString(BASIC);
+    let ast     = ParseString(BASIC).unwrap();
 
     let print   = Box::new(Type::Symbol("test".to_owned()));
     let hello       = Type::String("hello".to_owned());
-    let ten         = Type::Number(10.0);
+    let ten         = Type::Integer(10);
 
-    let expected       = vec![Type::Call{function: print, arguments: vec![ten, hello]}];
+    let expected       = vec![Type::Call{function: print, arguments: vec![ten, hello], span: Span::default()}];
 
     assert_eq!(ast, expected);
 
     // println!("beginning second test");
     // const BASIC2: &str     = r##"$ test  10   "hello"   "##;
-    // let ast     = ParseString(BASIC2);
+    // let ast     = ParseString(BASIC2).unwrap();
     // assert_eq!(ast, expected);
 }
 
@@ -149,15 +151,15 @@ pub fn functional_chained(){
 
     const CODE: &str     = r##"$ test 10 "hello" $ pop 2.4 "##;
 
-    let ast     = ParseString(CODE);
+    let ast     = ParseString(CODE).unwrap();
     let print   = Box::new(Type::Symbol("test".to_owned()));
     let hello       = Type::String("hello".to_owned());
-    let ten         = Type::Number(10.0);
+    let ten         = Type::Integer(10);
     let two_dot_four      = Type::Number(2.4);
     let pop               = Type::Call { function: Box::new(Type::Symbol("pop".to_owned())), 
-                                               arguments: vec![two_dot_four] };
+                                               arguments: vec![two_dot_four], span: Span::default() };
     
-    let expected       = vec![Type::Call{function: print, arguments: vec![ten, hello, pop]}];
+    let expected       = vec![Type::Call{function: print, arguments: vec![ten, hello, pop], span: Span::default()}];
 
     assert_eq!(ast, expected);
 }
@@ -167,13 +169,13 @@ pub fn functional_chained(){
 #[test]
 pub fn piping_regular() {
     const BASIC: &str     = r##"32 |> print("piping") "##;
-    let ast     = ParseString(BASIC);
+    let ast     = ParseString(BASIC).unwrap();
     let print   = Box::new(Type::Symbol("print".to_owned()));
     // let hello       = Type::String("hello".to_owned());
-    let hello               = Type::Number(32.0);
+    let hello               = Type::Integer(32);
     let piping      = Type::String("piping".to_owned());
 
-    let expected       = vec![Type::Call{function: print, arguments: vec![hello, piping]}];
+    let expected       = vec![Type::Call{function: print, arguments: vec![hello, piping], span: Span::default()}];
     assert_eq!(ast, expected);
 }
 
@@ -181,16 +183,16 @@ pub fn piping_regular() {
 #[test]
 pub fn piping_chain() {
     const BASIC: &str     = r##""hello" |> print("piping") |> soup("goop")"##;
-    let ast     = ParseString(BASIC);
+    let ast     = ParseString(BASIC).unwrap();
     let hello       = Type::String("hello".to_owned());
     let print   = Box::new(Type::Symbol("print".to_owned()));
     let piping      = Type::String("piping".to_owned());
 
-    let first_pipe       = Type::Call{function: print, arguments: vec![hello, piping]};
+    let first_pipe       = Type::Call{function: print, arguments: vec![hello, piping], span: Span::default()};
 
     let soup   = Box::new(Type::Symbol("soup".to_owned()));
     let goop      = Type::String("goop".to_owned());
-    let expected       = vec![Type::Call{function: soup, arguments: vec![first_pipe, goop]}];
+    let expected       = vec![Type::Call{function: soup, arguments: vec![first_pipe, goop], span: Span::default()}];
 
     assert_eq!(ast, expected);
 }
@@ -199,11 +201,11 @@ pub fn piping_chain() {
 #[test]
 pub fn while_loop() {
     const BASIC: &str     = r##"while true { print("hello") }"##;
-    let ast     = ParseString(BASIC);
+    let ast     = ParseString(BASIC).unwrap();
     let print   = Box::new(Type::Symbol("print".to_owned()));
     let hello       = Type::String("hello".to_owned());
     let true_value       = Type::Symbol("true".to_owned());
-    let while_loop      = Type::While { condition: Box::new(true_value), code: vec![Type::Call{function: print, arguments: vec![hello]}] };
+    let while_loop      = Type::While { condition: Box::new(true_value), code: vec![Type::Call{function: print, arguments: vec![hello], span: Span::default()}], span: Span::default() };
     let expected       = vec![while_loop];
     assert_eq!(ast, expected);
 }
@@ -213,13 +215,98 @@ pub fn while_loop() {
 #[test]
 pub fn if_else() {
     const BASIC: &str     = r##"if true { print("hello") } else { print("goodbye") }"##;
-    let ast     = ParseString(BASIC);
+    let ast     = ParseString(BASIC).unwrap();
     let print   = Box::new(Type::Symbol("print".to_owned()));
     let hello       = Type::String("hello".to_owned());
     let goodbye       = Type::String("goodbye".to_owned());
     let true_value       = Type::Symbol("true".to_owned());
-    let if_else      = Type::Conditional { condition: Box::new(true_value), then: vec![Type::Call{function: print.clone(), arguments: vec![hello]}], otherwise: Some(vec![Type::Call{function: print, arguments: vec![goodbye]}]) };
+    let if_else      = Type::Conditional { condition: Box::new(true_value), then: vec![Type::Call{function: print.clone(), arguments: vec![hello], span: Span::default()}], otherwise: Some(vec![Type::Call{function: print, arguments: vec![goodbye], span: Span::default()}]), span: Span::default() };
     let expected       = vec![if_else];
     assert_eq!(ast, expected);
 }
 
+
+#[test]
+pub fn spans() {
+    const CODE: &str     = "let x = 1\n  print(x + 2)";
+    let ast     = ParseString(CODE).unwrap();
+    let text = |node: &Type| {
+        let span = node.span().unwrap();
+        &CODE[span.start..span.end]
+    };
+    assert_eq!(text(&ast[0]), "let x = 1");
+    assert_eq!(text(&ast[1]), "print(x + 2)");
+    if let Type::Call { arguments, .. } = &ast[1] {
+        assert_eq!(text(&arguments[0]), "+");
+    } else {
+        panic!("expected a call, got {:?}", ast[1]);
+    }
+}
+
+#[test]
+pub fn return_statement() {
+    const CODE: &str     = "fn f(x) {\n    return x\n}\nfn g() {\n    return\n    x\n}";
+    let ast     = ParseString(CODE).unwrap();
+    let x       = Type::Symbol("x".to_owned());
+    let f       = Type::CreateFunction { name: Box::new(Type::Symbol("f".to_owned())), parameters: vec!["x".to_owned()], code: Rc::new(vec![Box::new(Type::Return { value: Some(Box::new(x.clone())), span: Span::default() })]), span: Span::default() };
+    let g       = Type::CreateFunction { name: Box::new(Type::Symbol("g".to_owned())), parameters: vec![], code: Rc::new(vec![Box::new(Type::Return { value: None, span: Span::default() }), Box::new(x)]), span: Span::default() };
+    assert_eq!(ast, vec![f, g]);
+}
+
+#[test]
+pub fn loop_control() {
+    const CODE: &str     = "while true { continue; break }\nbreakfast";
+    let ast     = ParseString(CODE).unwrap();
+    let code    = vec![Type::Continue { span: Span::default() }, Type::Break { span: Span::default() }];
+    let while_loop      = Type::While { condition: Box::new(Type::Symbol("true".to_owned())), code, span: Span::default() };
+    assert_eq!(ast, vec![while_loop, Type::Symbol("breakfast".to_owned())]);
+}
+
+#[test]
+pub fn lists() {
+    const CODE: &str     = "xs[0] = [1, f(2)[1]]";
+    let ast     = ParseString(CODE).unwrap();
+    let call = |name: &str, arguments: Vec<Type>| Type::Call { function: Box::new(Type::Symbol(name.to_owned())), arguments, span: Span::default() };
+    let inner = call("__index__", vec![call("f", vec![Type::Integer(2)]), Type::Integer(1)]);
+    let list = call("__list__", vec![Type::Integer(1), inner]);
+    assert_eq!(ast, vec![call("__setindex__", vec![Type::Symbol("xs".to_owned()), Type::Integer(0), list])]);
+}
+
+#[test]
+pub fn maps() {
+    const CODE: &str     = "if ok { m = {\"a\": 1, \"b\": {}} }";
+    let ast     = ParseString(CODE).unwrap();
+    let call = |name: &str, arguments: Vec<Type>| Type::Call { function: Box::new(Type::Symbol(name.to_owned())), arguments, span: Span::default() };
+    let map = call("__map__", vec![Type::String("a".to_owned()), Type::Integer(1), Type::String("b".to_owned()), call("__map__", vec![])]);
+    let assignment = Type::Assignment { variable: Box::new(Type::Symbol("m".to_owned())), value: Box::new(map), span: Span::default() };
+    let expected = Type::Conditional { condition: Box::new(Type::Symbol("ok".to_owned())), then: vec![assignment], otherwise: None, span: Span::default() };
+    assert_eq!(ast, vec![expected]);
+}
+
+#[test]
+pub fn for_loop() {
+    const CODE: &str     = "for i in 0..n + 1 { i }\nformat";
+    let ast     = ParseString(CODE).unwrap();
+    let call = |name: &str, arguments: Vec<Type>| Type::Call { function: Box::new(Type::Symbol(name.to_owned())), arguments, span: Span::default() };
+    let end = call("__add__", vec![Type::Symbol("n".to_owned()), Type::Integer(1)]);
+    let iterable = Box::new(call("__range__", vec![Type::Integer(0), end]));
+    let expected = Type::For { variable: "i".to_owned(), iterable, code: vec![Type::Symbol("i".to_owned())], span: Span::default() };
+    assert_eq!(ast, vec![expected, Type::Symbol("format".to_owned())]);
+}
+
+#[test]
+pub fn imports_and_members() {
+    const CODE: &str     = "import \"lib/m.rv\" as m\nfrom \"x.rv\" import f, g\nm.f(1).x";
+    let ast     = ParseString(CODE).unwrap();
+    let call = |name: &str, arguments: Vec<Type>| Type::Call { function: Box::new(Type::Symbol(name.to_owned())), arguments, span: Span::default() };
+    let string = |text: &str| Type::String(text.to_owned());
+    let declare = |name: &str, value: Type| Type::VariableDeclaration { variable: Box::new(Type::Symbol(name.to_owned())), value: Box::new(value), span: Span::default() };
+    let import = declare("m", call("__import__", vec![string("lib/m.rv")]));
+    let from = Type::Invocation { code: vec![
+        declare("f", call("__import__", vec![string("x.rv"), string("f")])),
+        declare("g", call("__import__", vec![string("x.rv"), string("g")])),
+    ] };
+    let member = call("__member__", vec![Type::Symbol("m".to_owned()), string("f")]);
+    let called = Type::Call { function: Box::new(member), arguments: vec![Type::Integer(1)], span: Span::default() };
+    assert_eq!(ast, vec![import, from, call("__member__", vec![called, string("x")])]);
+}
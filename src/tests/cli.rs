@@ -0,0 +1,28 @@
+use crate::cli::{parse, Command, Options};
+
+fn args(line: &str) -> Result<Options, String> {
+    parse(line.split_whitespace().map(str::to_string))
+}
+
+#[test]
+pub fn commands(){
+    let run = args("--debug run script.rv one --vm 3").unwrap();
+    let script = Command::Run { file: "script.rv".to_string(), args: vec!["one".to_string(), "--vm".to_string(), "3".to_string()] };
+    assert_eq!(run, Options { command: script, debug: true, vm: false });
+
+    assert_eq!(args("check --vm a.rv").unwrap(), Options { command: Command::Check { file: "a.rv".to_string() }, debug: false, vm: true });
+    assert_eq!(args("fmt a.rv").unwrap().command, Command::Fmt { file: "a.rv".to_string() });
+    assert_eq!(args("ast a.rv").unwrap().command, Command::Ast { file: "a.rv".to_string() });
+    assert_eq!(args("--vm repl").unwrap(), Options { command: Command::Repl, debug: false, vm: true });
+    assert_eq!(args("").unwrap().command, Command::Help);
+    assert_eq!(args("run --help").unwrap().command, Command::Help);
+}
+
+#[test]
+pub fn mistakes(){
+    assert_eq!(args("run"), Err("`run` needs a file".to_string()));
+    assert_eq!(args("compile a.rv"), Err("unknown command `compile`".to_string()));
+    assert_eq!(args("--fast run a.rv"), Err("unknown option `--fast`".to_string()));
+    assert_eq!(args("check a.rv b.rv"), Err("unexpected argument `b.rv`".to_string()));
+    assert_eq!(args("repl a.rv"), Err("unexpected argument `a.rv`".to_string()));
+}
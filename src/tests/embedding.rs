@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+
+use super::{recording, ENGINES};
+use crate::interpreter::convert::Callback;
+use crate::interpreter::error::{RavenError, RavenResult};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::value::Value;
+use crate::parser::parser::Type;
+
+/// the value of `code` printed the way the repl shows it, the same when run again on each engine
+fn run(i: &mut Interpreter, code: &str) -> RavenResult<String> {
+    let values = ENGINES.iter()
+        .map(|&vm| Ok(super::run(i, code, vm)?.map(|value| value.borrow().toString())))
+        .collect::<RavenResult<Vec<_>>>()?;
+    assert!(values.windows(2).all(|pair| pair[0] == pair[1]), "the engines disagree: {:?}", values);
+    Ok(values[0].clone().unwrap_or_default())
+}
+
+#[test]
+pub fn typed_functions(){
+    let (mut i, _) = recording();
+    i.register("hypot", |a: f64, b: f64| a.hypot(b));
+    i.register("repeat", |text: String, times: i64| text.repeat(times as usize));
+    i.register("both", |a: bool, b: bool| a && b);
+    i.register("total", |numbers: Vec<f64>| numbers.iter().sum::<f64>());
+    i.register("lengths", |words: BTreeMap<String, String>| words.into_iter().map(|(key, word)| (key, word.len() as i64)).collect::<BTreeMap<_, _>>());
+    i.register("nothing", || ());
+
+    assert_eq!(run(&mut i, "hypot(3, 4.0)").unwrap(), "5");
+    assert_eq!(run(&mut i, "repeat(\"ab\", 3)").unwrap(), "ababab");
+    assert_eq!(run(&mut i, "both(true, 1 < 2)").unwrap(), "true");
+    assert_eq!(run(&mut i, "total([1, 2.5, 3])").unwrap(), "6.5");
+    assert_eq!(run(&mut i, "lengths({\"a\": \"raven\", \"b\": \"\"})").unwrap(), "{\"a\": 5, \"b\": 0}");
+    assert_eq!(run(&mut i, "nothing()").unwrap(), "");
+}
+
+#[test]
+pub fn checked_arguments(){
+    let (mut i, _) = recording();
+    i.register("hypot", |a: f64, b: f64| a.hypot(b));
+    i.register("repeat", |text: String, times: i64| text.repeat(times as usize));
+
+    let error = |i: &mut Interpreter, code: &str| run(i, code).unwrap_err().inner().clone();
+    assert_eq!(error(&mut i, "hypot(1)"), RavenError::ArityMismatch { expected: 2, found: 1 });
+    assert_eq!(error(&mut i, "hypot(1, 2, 3)"), RavenError::ArityMismatch { expected: 2, found: 3 });
+    assert_eq!(error(&mut i, "hypot(1, \"2\")").to_string(), "cannot use as a number string");
+    assert_eq!(error(&mut i, "repeat(\"a\", 1.5)").to_string(), "cannot use as an integer number");
+}
+
+#[test]
+pub fn optional_arguments(){
+    let (mut i, _) = recording();
+    i.register("greet", |name: String, greeting: Option<String>| format!("{} {}", greeting.as_deref().unwrap_or("hello"), name));
+    i.register("find", |items: Vec<i64>, item: i64| items.iter().position(|x| *x == item).map(|at| at as i64));
+
+    assert_eq!(run(&mut i, "greet(\"ann\")").unwrap(), "hello ann");
+    assert_eq!(run(&mut i, "greet(\"ann\", \"bye\")").unwrap(), "bye ann");
+    assert_eq!(run(&mut i, "find([4, 5], 5)").unwrap(), "1");
+    // `None` is void, which cannot be stored
+    assert_eq!(run(&mut i, "let at = find([4, 5], 6)").unwrap_err().inner(), &RavenError::VoidValue("a variable's value".to_string()));
+}
+
+#[test]
+pub fn callbacks_and_errors(){
+    let (mut i, _) = recording();
+    i.register("apply", |function: Callback, items: Vec<f64>| -> RavenResult<Vec<f64>> {
+        items.into_iter().map(|item| function.call((item,))).collect()
+    });
+    i.register("checked", |x: i64| if x < 0 { Err(RavenError::Runtime("negative".to_string())) } else { Ok(x) });
+
+    assert_eq!(run(&mut i, "let scale = 3\napply((x) => { x * scale }, [1, 2])").unwrap(), "[3, 6]");
+    assert_eq!(run(&mut i, "apply(len, [1])").unwrap_err().inner().to_string(), "cannot take the length of number");
+    assert_eq!(run(&mut i, "apply(1, [])").unwrap_err().inner().to_string(), "cannot call integer");
+    assert_eq!(run(&mut i, "checked(2)").unwrap(), "2");
+    assert_eq!(run(&mut i, "checked(-2)").unwrap_err().inner(), &RavenError::Runtime("negative".to_string()));
+}
+
+#[test]
+pub fn calling_script_functions(){
+    let plugin = "fn area(w, h) { w * h }\nfn greet(name) { \"hi \" + name }\nfn log(x) { print(x) }\nlet limit = 3\nlet hits = []";
+    for vm in ENGINES {
+        let (mut i, output) = recording();
+        super::run(&mut i, plugin, vm).unwrap();
+
+        assert_eq!(i.call("area", (2i64, 3.5)).unwrap().get::<f64>().unwrap(), 7.0);
+        assert_eq!(i.call("greet", vec!["ann"]).unwrap().get::<String>().unwrap(), "hi ann");
+        assert!(i.call("log", (1i64,)).unwrap().isVoid());
+        assert_eq!(*output.borrow(), ["1"]);
+        // leaving arguments out binds the ones given, like it does in raven
+        let double = i.call("area", (2i64,)).unwrap();
+        assert_eq!(double.typeName(), "function");
+        assert_eq!(double.call((21i64,)).unwrap().get::<i64>().unwrap(), 42);
+
+        assert_eq!(i.get_global("limit").unwrap().get::<i64>().unwrap(), 3);
+        assert_eq!(i.get_global("len").unwrap().call((vec![1i64, 2],)).unwrap().to_string(), "2");
+        assert!(i.get_global("missing").is_none());
+
+        // values share their object with the script
+        let hits = i.get_global("hits").unwrap();
+        i.call("push", (hits.clone(), "a")).unwrap();
+        assert_eq!(hits.get::<Vec<String>>().unwrap(), ["a"]);
+        assert_eq!(Value::new(vec![1.5]).unwrap().to_string(), "[1.5]");
+    }
+}
+
+#[test]
+pub fn failing_calls(){
+    let (mut i, _) = recording();
+    i.run("fn area(w, h) { w * h }\nlet limit = 3".to_string(), false).unwrap();
+    assert_eq!(i.call("nothing", ()).unwrap_err(), RavenError::UndefinedVariable("nothing".to_string()));
+    assert_eq!(i.call("limit", ()).unwrap_err(), RavenError::NotCallable("limit".to_string()));
+    assert_eq!(i.call("area", (1i64, 2i64, 3i64)).unwrap_err(), RavenError::ArityMismatch { expected: 2, found: 3 });
+    assert_eq!(i.call("area", (true, 2i64)).unwrap_err().inner().to_string(), "cannot multiply bool and integer");
+    assert_eq!(i.call("area", (2i64, 2i64)).unwrap().get::<String>().unwrap_err().to_string(), "cannot use as a string integer");
+    assert_eq!(Value::void().get::<f64>().unwrap_err(), RavenError::VoidValue("a value".to_string()));
+    assert_eq!(Value::void().get::<Option<f64>>().unwrap(), None);
+}
+
+#[test]
+pub fn eval(){
+    let mut i = Interpreter::with_stdlib();
+    i.addObject("price", Type::Number(2.5));
+    i.addObject("qty", Type::Integer(4));
+    assert_eq!(i.eval("price * qty").unwrap().get::<f64>().unwrap(), 10.0);
+    assert!(i.eval("qty > 3 && price < 3").unwrap().get::<bool>().unwrap());
+    assert_eq!(i.eval("let total = 0\nfor n in 1..4 { total += n }\ntotal").unwrap().get::<i64>().unwrap(), 6);
+    // the global scope lives on between calls
+    assert_eq!(i.eval("total * 2").unwrap().to_string(), "12");
+    assert!(i.eval("let x = 1").unwrap().isVoid());
+    assert_eq!(i.eval("discount").unwrap_err().inner(), &RavenError::UndefinedVariable("discount".to_string()));
+    assert!(matches!(i.eval("price *"), Err(RavenError::Syntax(_))));
+}
@@ -0,0 +1,163 @@
+use crate::interpreter::error::RavenError;
+use crate::interpreter::interpreter::Interpreter;
+use crate::parser::parser::{ParseFile, ParseString};
+use crate::parser::span::Span;
+
+use super::{everywhere, failure, recording};
+
+#[test]
+pub fn undefined_variable(){
+    assert_eq!(failure("print(missing)"), RavenError::UndefinedVariable("missing".to_string()));
+    assert_eq!(failure("nope = 2"), RavenError::UndefinedVariable("nope".to_string()));
+}
+
+#[test]
+pub fn type_mismatch(){
+    let expected = RavenError::TypeMismatch { operation: "subtract".to_string(), operands: vec!["number".to_string(), "string".to_string()] };
+    assert_eq!(failure(r##"let x = 1.5 - "one""##), expected);
+    assert_eq!(expected.to_string(), "cannot subtract number and string");
+
+    assert_eq!(failure("if 1.5 { print(1) }"), RavenError::TypeMismatch { operation: "branch on".to_string(), operands: vec!["number".to_string()] });
+}
+
+#[test]
+pub fn arity_and_calls(){
+    assert_eq!(failure("fn f(a) { a }\nf(1, 2)"), RavenError::ArityMismatch { expected: 1, found: 2 });
+    assert_eq!(failure("let x = 3\nx(1)"), RavenError::NotCallable("x".to_string()));
+    assert_eq!(failure("fn nothing() { let y = 1 }\nprint(nothing())"), RavenError::VoidValue("an argument".to_string()));
+}
+
+#[test]
+pub fn operators_called_by_name(){
+    assert_eq!(failure("__add__(1)"), RavenError::ArityMismatch { expected: 2, found: 1 });
+    assert_eq!(failure("__lt__(1, 2, 3)"), RavenError::ArityMismatch { expected: 2, found: 3 });
+
+    let mut i = Interpreter::new();
+    assert_eq!(i.call("__add__", (1i64,)), Err(RavenError::ArityMismatch { expected: 2, found: 1 }));
+    assert_eq!(i.call("__not__", (true, false)), Err(RavenError::ArityMismatch { expected: 1, found: 2 }));
+    assert_eq!(i.call("__add__", (1i64, 2i64)).unwrap().get::<i64>().unwrap(), 3);
+}
+
+#[test]
+pub fn misplaced_return(){
+    let error = failure("print(1)\nreturn 2");
+    assert_eq!(error, RavenError::Misplaced { keyword: "return".to_string(), context: "function".to_string() });
+    assert_eq!(error.to_string(), "`return` used outside of a function");
+}
+
+#[test]
+pub fn misplaced_loop_control(){
+    assert_eq!(failure("if true { break }"), RavenError::Misplaced { keyword: "break".to_string(), context: "loop".to_string() });
+    assert_eq!(failure("fn skip() { continue }\nwhile true { skip() }").to_string(), "`continue` used outside of a loop");
+}
+
+#[test]
+pub fn misplaced_statements_located(){
+    let cases = [
+        ("print(1)\nreturn 2", "return", (2, 1)),
+        ("fn f() { 1 }\nif true {\n  return f()\n}", "return", (3, 3)),
+        ("let x = 0\nif true { break }", "break", (2, 11)),
+        ("fn skip() { continue }\nwhile true { skip() }", "continue", (1, 13)),
+    ];
+    for (code, statement, position) in cases {
+        let errors = everywhere(code, |_| {}).into_iter().map(|(result, _)| result.unwrap_err()).collect::<Vec<_>>();
+        assert!(errors.windows(2).all(|pair| pair[0] == pair[1]), "the engines disagree: {:?}", errors);
+        let span = errors[0].span().unwrap();
+        assert_eq!(&code[span.start..span.end], statement);
+        assert_eq!(Span::lineCol(code, span.start), position);
+    }
+}
+
+#[test]
+pub fn interpreter_survives_errors(){
+    let (mut i, output) = recording();
+    assert!(i.run("print(1 + true)".to_string(), false).is_err());
+    i.run(r##"print("still alive")"##.to_string(), false).unwrap();
+    assert_eq!(*output.borrow(), vec!["still alive".to_string()]);
+}
+
+#[test]
+pub fn located(){
+    const CODE: &str = "let a = 1\nlet b = a +  true\nprint(b)";
+    for (result, _) in everywhere(CODE, |_| {}) {
+        let error = result.unwrap_err();
+        let span = error.span().unwrap();
+        assert_eq!(&CODE[span.start..span.end], "+");
+        assert_eq!(Span::lineCol(CODE, span.start), (2, 11));
+    }
+}
+
+#[test]
+pub fn rendered(){
+    crate::color::disable();
+    const CODE: &str = "fn f(x) {\n    x + nothing\n}\nf(2)";
+    let (mut i, _) = recording();
+    let error = i.run(CODE.to_string(), false).unwrap_err();
+    let expected = "\
+error: cannot find variable `nothing`
+ --> main.rv:2:7
+  |
+2 |     x + nothing
+  |       ^";
+    assert_eq!(error.render(CODE, "main.rv"), expected);
+}
+
+fn syntax(code: &str) -> String {
+    match ParseString(code) {
+        Err(RavenError::Syntax(error)) => error.message,
+        other => panic!("expected a syntax error, got {:?}", other),
+    }
+}
+
+#[test]
+pub fn syntax_errors(){
+    assert_eq!(syntax("fn f() {\n  if x {\n    print(1)\n  }\n"), "expected `}` to close block opened at 1:8, found end of file");
+    assert_eq!(syntax("print(1, 2"), "expected `)` to close `(` opened at 1:6, found end of file");
+    assert_eq!(syntax("let x = "), "expected an expression, found end of file");
+    assert_eq!(syntax("print(\"abc"), "unterminated string opened at 1:7");
+    assert_eq!(syntax("x = 1.2.3"), "invalid number `1.2.3`");
+    assert_eq!(syntax("print(1) )"), "unexpected `)`");
+
+    let error = ParseString("let a = 1\nwhile a < 3 { a = }").unwrap_err();
+    let span = error.span().unwrap();
+    assert_eq!(Span::lineCol("let a = 1\nwhile a < 3 { a = }", span.start), (2, 19));
+    assert_eq!(error.to_string(), "expected an expression, found `}`");
+}
+
+#[test]
+pub fn syntax_errors_are_returned(){
+    let (mut i, output) = recording();
+    let error = i.run("print(1".to_string(), false).unwrap_err();
+    assert!(matches!(error, RavenError::Syntax(_)));
+    assert!(output.borrow().is_empty());
+
+    assert!(matches!(ParseFile("does/not/exist.rv"), Err(RavenError::Io(_))));
+}
+
+#[test]
+pub fn list_errors(){
+    assert_eq!(failure("let xs = [1, 2]\nprint(xs[2])"), RavenError::Runtime("index 2 is out of bounds for length 2".to_string()));
+    assert_eq!(failure("let xs = [1]\nxs[\"a\"] = 2").to_string(), "cannot assign into list and string");
+    assert_eq!(failure("pop([])").to_string(), "cannot pop from an empty list");
+}
+
+#[test]
+pub fn map_errors(){
+    assert_eq!(failure("let m = { \"a\": 1 }\nprint(m[\"b\"])"), RavenError::Runtime("no key \"b\" in map".to_string()));
+    assert_eq!(failure("let m = {}\nm[1] = 2").to_string(), "cannot assign into map and integer");
+}
+
+#[test]
+pub fn for_errors(){
+    assert_eq!(failure("for i in 0..2 { i }\nprint(i)"), RavenError::UndefinedVariable("i".to_string()));
+    assert_eq!(failure("for i in 5 { i }").to_string(), "cannot iterate over integer");
+}
+
+#[test]
+pub fn integer_errors(){
+    assert_eq!(failure("print(1 / 0)"), RavenError::Runtime("division by zero".to_string()));
+    assert_eq!(failure("print(9223372036854775807 + 1)"), RavenError::Runtime("integer overflow".to_string()));
+    assert_eq!(failure("print(\"ab\" * -1)").to_string(), "cannot repeat a string -1 times");
+    assert_eq!(failure("print(\"ab\" * 1.5)").to_string(), "cannot multiply string and number");
+    assert_eq!(failure("print(99999999999999999999)").to_string(), "invalid number `99999999999999999999`");
+}
@@ -0,0 +1,190 @@
+use std::fs::read_to_string;
+use std::rc::Rc;
+
+use proptest::prelude::*;
+use proptest::sample::select;
+
+use crate::parser::format::format;
+use crate::parser::parser::{ParseString, Type};
+use crate::parser::span::Span;
+
+const NAMES: [&str; 5] = ["a", "b", "xs", "total", "f_2"];
+
+const OPERATORS: [&str; 16] = [
+    "__add__", "__sub__", "__mul__", "__div__", "__pow__", "__mod__", "__range__",
+    "__eq__", "__ne__", "__le__", "__ge__", "__lt__", "__gt__", "__and__", "__or__", "__index__",
+];
+
+fn call(name: &str, arguments: Vec<Type>) -> Type {
+    Type::Call { function: Box::new(Type::Symbol(name.to_string())), arguments, span: Span::default() }
+}
+
+/// what `import "path" as name` lowers to, or with `from` one of the names taken from the module
+fn import(name: &str, path: &str, from: Option<&str>) -> Type {
+    let arguments = [Some(path), from].into_iter().flatten().map(|text| Type::String(text.to_string())).collect();
+    Type::VariableDeclaration { variable: Box::new(Type::Symbol(name.to_string())), value: Box::new(call("__import__", arguments)), span: Span::default() }
+}
+
+fn symbol() -> impl Strategy<Value = String> {
+    select(&NAMES[..]).prop_map(str::to_string)
+}
+
+/// whatever can stand where the grammar wants an `Operation`, like the condition of an `if`
+fn operation(node: &Type) -> bool {
+    !matches!(node, Type::VariableDeclaration { .. } | Type::Assignment { .. } | Type::CreateFunction { .. } |
+        Type::Return { .. } | Type::Break { .. } | Type::Continue { .. } | Type::Comment(_) | Type::Blank)
+}
+
+/// a block with single empty lines sprinkled between its statements, never at either end
+fn block(statement: impl Strategy<Value = Type>) -> impl Strategy<Value = Vec<Type>> {
+    prop::collection::vec((statement, any::<bool>()), 0..4).prop_map(|lines| {
+        let last = lines.len().saturating_sub(1);
+        let mut block = vec![];
+        for (i, (line, blank)) in lines.into_iter().enumerate() {
+            block.push(line);
+            if blank && i < last {
+                block.push(Type::Blank);
+            }
+        }
+        block
+    })
+}
+
+fn statement(expression: BoxedStrategy<Type>) -> BoxedStrategy<Type> {
+    let span = Span::default();
+    prop_oneof![
+        4 => expression.clone(),
+        1 => (symbol(), expression.clone()).prop_map(move |(name, value)| Type::VariableDeclaration { variable: Box::new(Type::Symbol(name)), value: Box::new(value), span }),
+        1 => (symbol(), expression.clone()).prop_map(move |(name, value)| Type::Assignment { variable: Box::new(Type::Symbol(name)), value: Box::new(value), span }),
+        1 => (symbol(), select(&OPERATORS[..6]), expression.clone()).prop_map(move |(name, operator, amount)| {
+            let value = call(operator, vec![Type::Symbol(name.clone()), amount]);
+            Type::Assignment { variable: Box::new(Type::Symbol(name)), value: Box::new(value), span }
+        }),
+        1 => (expression.clone(), expression.clone(), expression.clone()).prop_map(|(target, index, value)| call("__setindex__", vec![target, index, value])),
+        1 => prop::option::of(expression.clone()).prop_map(move |value| Type::Return { value: value.map(Box::new), span }),
+        1 => Just(Type::Break { span }),
+        1 => Just(Type::Continue { span }),
+        1 => "[a-z ]{0,10}".prop_map(Type::Comment),
+        1 => (symbol(), "[a-z/]{1,6}\\.rv").prop_map(move |(name, path)| import(&name, &path, None)),
+        1 => ("[a-z/]{1,6}\\.rv", prop::collection::vec(symbol(), 1..3)).prop_map(|(path, names)| {
+            Type::Invocation { code: names.iter().map(|name| import(name, &path, Some(name))).collect() }
+        }),
+    ].boxed()
+}
+
+/// trees shaped like the ones the parser builds, spans aside
+fn tree() -> impl Strategy<Value = Type> {
+    let span = Span::default();
+    let leaf = prop_oneof![
+        any::<i64>().prop_map(Type::Integer),
+        any::<f32>().prop_filter("literals are finite", |number| number.is_finite()).prop_map(Type::Number),
+        symbol().prop_map(Type::Symbol),
+        "[a-z ]{0,8}".prop_map(Type::String),
+    ];
+    leaf.prop_recursive(4, 48, 4, move |inner| {
+        let condition = inner.clone().prop_filter("conditions are operations", operation);
+        let code = || block(statement(inner.clone()));
+        let parameters = prop::collection::vec(symbol(), 0..3);
+        prop_oneof![
+            (select(&OPERATORS[..]), inner.clone(), inner.clone()).prop_map(|(operator, x, y)| call(operator, vec![x, y])),
+            inner.clone().prop_map(|x| call("__not__", vec![x])),
+            (inner.clone(), symbol()).prop_map(|(target, name)| call("__member__", vec![target, Type::String(name)])),
+            (inner.clone(), symbol(), prop::collection::vec(inner.clone(), 0..3)).prop_map(move |(target, name, arguments)| {
+                Type::Call { function: Box::new(call("__member__", vec![target, Type::String(name)])), arguments, span }
+            }),
+            prop::collection::vec(inner.clone(), 0..3).prop_map(|items| call("__list__", items)),
+            prop::collection::vec(inner.clone(), 0..3).prop_map(|entries| call("__map__", entries.iter().flat_map(|value| [Type::String("k".to_string()), value.clone()]).collect())),
+            (symbol(), prop::collection::vec(inner.clone(), 0..3)).prop_map(|(name, arguments)| call(&name, arguments)),
+            (condition.clone(), code(), prop::option::of(code())).prop_map(move |(condition, then, otherwise)| Type::Conditional { condition: Box::new(condition), then, otherwise, span }),
+            (condition.clone(), code()).prop_map(move |(condition, code)| Type::While { condition: Box::new(condition), code, span }),
+            (symbol(), condition, code()).prop_map(move |(variable, iterable, code)| Type::For { variable, iterable: Box::new(iterable), code, span }),
+            (prop::option::of(symbol()), parameters, code()).prop_map(move |(name, parameters, code)| {
+                let name = Box::new(Type::Symbol(name.unwrap_or_default()));
+                Type::CreateFunction { name, code: Rc::new(code.into_iter().map(Box::new).collect()), parameters, span }
+            }),
+        ]
+    })
+}
+
+fn roundtrip(code: &[Type]) {
+    let formatted = format(code);
+    match ParseString(&formatted) {
+        Ok(parsed) => assert_eq!(parsed, code, "formatted as\n{}", formatted),
+        Err(error) => panic!("formatted as\n{}\nwhich does not parse: {}", formatted, error),
+    }
+    assert_eq!(format(&ParseString(&formatted).unwrap()), formatted);
+}
+
+#[test]
+pub fn canonical_style(){
+    let code = "let  x=[1,2 ,3]\nfn add(a,b){return a+b}\n\n\n// sum it up\nfor i in 0..len(x) {total+=x[i]*(2 - 1)}\nif(a<b){x++} else { if a == b { xs[0]={ \"k\" : 1.0 } } }";
+    let expected = "\
+let x = [1, 2, 3]
+fn add(a, b) {
+    return a + b
+}
+
+// sum it up
+for i in 0..len(x) {
+    total += x[i] * (2 - 1)
+}
+if a < b {
+    x++
+} else if a == b {
+    xs[0] = {\"k\": 1.0}
+}
+";
+    assert_eq!(format(&ParseString(code).unwrap()), expected);
+}
+
+#[test]
+pub fn parentheses(){
+    let cases = [
+        ("(a + b) * c", "(a + b) * c"),
+        ("a - (b - c)", "a - (b - c)"),
+        ("((a * b)) + c", "a * b + c"),
+        ("!(a..b)", "!(a..b)"),
+        ("a == (b == c)", "a == (b == c)"),
+        ("if ((a + b) * 2 > c) {}", "if ((a + b) * 2 > c) {}"),
+        ("x = (if a { 1 } else { 2 }) + 1", "x = (if a {\n    1\n} else {\n    2\n}) + 1"),
+        ("__add__(a && b, 1)", "__add__(a && b, 1)"),
+        ("__index__(1, 0)", "__index__(1, 0)"),
+    ];
+    for (code, expected) in cases {
+        let formatted = format(&ParseString(code).unwrap());
+        assert_eq!(formatted.trim_end(), expected, "formatting {}", code);
+        roundtrip(&ParseString(code).unwrap());
+    }
+}
+
+#[test]
+pub fn lines_that_would_join(){
+    // without the `;` these read as `f(a + b)` and `x - 1`
+    roundtrip(&ParseString("f;\n(a + b) * 2\nx;\n-1").unwrap());
+    roundtrip(&ParseString("let g = f;\n(x) => { x }").unwrap());
+}
+
+#[test]
+pub fn modules(){
+    let code = "import  \"lib/m.rv\"  as m\nfrom \"x.rv\" import f,g\nprint(m.f(1).x, xs[0](2), __member__(1 + 2, \"y\"))";
+    let expected = "import \"lib/m.rv\" as m\nfrom \"x.rv\" import f, g\nprint(m.f(1).x, xs[0](2), __member__(1 + 2, \"y\"))\n";
+    assert_eq!(format(&ParseString(code).unwrap()), expected);
+    roundtrip(&ParseString(code).unwrap());
+}
+
+#[test]
+pub fn scripts(){
+    for script in ["basics", "loops", "returns", "control", "lists", "maps", "for", "integers", "closures", "stdlib", "modules/main", "modules/geometry", "modules/lib/shout"] {
+        let code = read_to_string(format!("src/tests/scripts/{}.rv", script)).unwrap();
+        roundtrip(&ParseString(&code).unwrap());
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn formatting_round_trips(code in block(statement(tree().boxed()))) {
+        roundtrip(&code);
+    }
+}
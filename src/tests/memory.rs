@@ -0,0 +1,64 @@
+use std::fs::read_to_string;
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+use super::{run, ENGINES};
+use crate::interpreter::interpreter::{FunctionTypes, Interpreter};
+use crate::parser::parser::{Func, Type};
+
+/// runs `code` in an interpreter holding a builtin that owns a token, and tells whether dropping the
+/// interpreter let go of the token, along with everything that kept the builtin alive and every object
+/// the code passed to `watch`
+fn freed(code: &str, vm: bool) -> bool {
+    let token = Rc::new(());
+    let watched = Rc::new(RefCell::new(vec![]));
+    {
+        let mut i = Interpreter::with_stdlib();
+        i.addObject("__file__", Type::String("src/tests/scripts/modules/main.rv".to_string()));
+        let held = token.clone();
+        i.addFunction("print", 1, move |_, _| {
+            let _ = &held;
+            Ok(None)
+        });
+        let watching = watched.clone();
+        i.addFunction("watch", 1, move |_, args| {
+            watching.borrow_mut().push(Rc::downgrade(&args[0]));
+            Ok(None)
+        });
+        run(&mut i, code, vm).unwrap();
+    }
+    let watched = watched.borrow();
+    Rc::strong_count(&token) == 1 && watched.iter().all(|object: &Weak<_>| object.upgrade().is_none())
+}
+
+#[test]
+pub fn interpreters_free_everything(){
+    for vm in ENGINES {
+        assert!(freed("print(1)", vm));
+        assert!(freed(&read_to_string("src/tests/scripts/closures.rv").unwrap(), vm));
+        assert!(freed(&read_to_string("src/tests/scripts/modules/main.rv").unwrap(), vm));
+        assert!(freed("let xs = []\nfn keep() { xs }\npush(xs, keep, xs)", vm));
+    }
+}
+
+#[test]
+pub fn containers_holding_themselves_are_freed(){
+    for vm in ENGINES {
+        assert!(freed("let xs = []\npush(xs, xs)\nwatch(xs)", vm));
+        assert!(freed("let m = {}\nm[\"self\"] = m\nwatch(m)", vm));
+        assert!(freed("let a = []\nlet b = { \"a\": a }\npush(a, b)\nwatch(a)\nwatch(b)", vm));
+    }
+}
+
+#[test]
+pub fn builtins_compare_by_identity(){
+    let builtin = |function: Func| FunctionTypes::BuiltIn { Function: function, parameters: 0 };
+    let first = Func::new(Box::new(|_, _| Ok(None)));
+    let same = Func::new(Box::new(|_, _| Ok(None)));
+    assert_eq!(builtin(first.clone()), builtin(first.clone()));
+    assert_ne!(builtin(first), builtin(same));
+
+    let i = Interpreter::with_stdlib();
+    assert_eq!(i.get_global("len"), i.get_global("len"));
+    assert_ne!(i.get_global("len"), i.get_global("pop"));
+}
@@ -1 +1,68 @@
-mod ast;
\ No newline at end of file
+use std::{cell::RefCell, rc::Rc};
+
+use crate::interpreter::error::{RavenError, RavenResult};
+use crate::interpreter::interpreter::{Interpreter, Object};
+
+mod ast;
+mod cli;
+mod embedding;
+mod errors;
+mod format;
+mod memory;
+mod modules;
+#[cfg(feature = "repl")]
+mod repl;
+mod recursion;
+mod resolver;
+mod stdlib;
+#[cfg(feature = "vm")]
+mod vm;
+mod wasm;
+
+/// an interpreter whose `print` records each line instead of writing to stdout
+pub fn recording() -> (Interpreter, Rc<RefCell<Vec<String>>>) {
+    let output = Rc::new(RefCell::new(vec![]));
+    let mut i = Interpreter::with_stdlib();
+    let out = output.clone();
+    i.addFunction("print", 1, move |_, args|{
+        let text = args.into_iter().map(|e| e.borrow().toString()).collect::<Vec<_>>().join(" ");
+        out.borrow_mut().push(text);
+        Ok(None)
+    });
+    (i, output)
+}
+
+/// the engines every script runs on: the tree walker, then the vm when it is built
+#[cfg(feature = "vm")]
+pub const ENGINES: [bool; 2] = [false, true];
+#[cfg(not(feature = "vm"))]
+pub const ENGINES: [bool; 1] = [false];
+
+/// runs `code` in `i`, on the vm or the tree walker
+pub fn run(i: &mut Interpreter, code: &str, vm: bool) -> RavenResult<Option<Object>> {
+    #[cfg(feature = "vm")]
+    if vm {
+        return i.runVM(code.to_string(), false)
+    }
+    assert!(!vm, "the vm is not built");
+    i.run(code.to_string(), false)
+}
+
+/// runs `code` on each engine in a fresh `recording()` interpreter that `setup` gets first, and gives back
+/// the results along with what each printed
+pub fn everywhere(code: &str, setup: impl Fn(&mut Interpreter)) -> Vec<(RavenResult<Option<Object>>, Vec<String>)> {
+    ENGINES.iter().map(|&vm| {
+        let (mut i, output) = recording();
+        setup(&mut i);
+        let result = run(&mut i, code, vm);
+        let output = output.borrow().clone();
+        (result, output)
+    }).collect()
+}
+
+/// the error `code` fails with, the same on each engine
+pub fn failure(code: &str) -> RavenError {
+    let errors = everywhere(code, |_| {}).into_iter().map(|(result, _)| result.unwrap_err().inner().clone()).collect::<Vec<_>>();
+    assert!(errors.windows(2).all(|pair| pair[0] == pair[1]), "the engines disagree: {:?}", errors);
+    errors[0].clone()
+}
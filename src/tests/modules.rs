@@ -0,0 +1,57 @@
+use std::fs::read_to_string;
+
+use super::everywhere;
+use crate::interpreter::error::{RavenError, RavenResult};
+use crate::interpreter::interpreter::Object;
+use crate::parser::parser::Type;
+
+const DIRECTORY: &str = "src/tests/scripts/modules";
+
+/// runs `code` as if it were a file in the modules directory, on each engine
+fn both(code: &str) -> Vec<(RavenResult<Option<Object>>, Vec<String>)> {
+    everywhere(code, |i| i.addObject("__file__", Type::String(format!("{}/main.rv", DIRECTORY))))
+}
+
+#[test]
+pub fn imports(){
+    let code = read_to_string(format!("{}/main.rv", DIRECTORY)).unwrap();
+    for (result, output) in both(&code) {
+        result.unwrap();
+        assert_eq!(output, ["loading geometry", "9 16", "0 0", "hello!0", "1 2 3", "3", "10"]);
+    }
+}
+
+#[test]
+pub fn missing_modules_and_names(){
+    for (result, _) in both("import \"nowhere.rv\" as m") {
+        assert!(result.unwrap_err().to_string().starts_with("cannot read src/tests/scripts/modules/nowhere.rv"));
+    }
+    for (result, _) in both("from \"geometry.rv\" import square, cube") {
+        assert_eq!(result.unwrap_err().to_string(), "`cube` is not exported by geometry.rv");
+    }
+    for (result, _) in both("import \"geometry.rv\" as g\ng.cube(2)") {
+        assert_eq!(result.unwrap_err().inner(), &RavenError::missing("cube"));
+    }
+}
+
+#[test]
+pub fn circular_imports(){
+    for (result, _) in both("import \"cycle_a.rv\" as a") {
+        let message = result.unwrap_err().to_string();
+        let cycle = ["cycle_a", "cycle_b", "cycle_a"].map(|name| format!("{}/{}.rv", DIRECTORY, name)).join(" -> ");
+        assert!(message.ends_with(&format!("circular import: {}", cycle)), "{}", message);
+    }
+}
+
+#[test]
+pub fn errors_point_into_the_module(){
+    let code = "import \"broken.rv\" as broken";
+    for (result, _) in both(code) {
+        let error = result.unwrap_err();
+        assert_eq!(error.to_string(), "in src/tests/scripts/modules/broken.rv: cannot subtract integer and string");
+        crate::color::disable();
+        let rendered = error.render(code, "main.rv");
+        assert!(rendered.contains("--> src/tests/scripts/modules/broken.rv:2:"), "{}", rendered);
+        assert!(rendered.contains("let wrong = fine - \"one\""), "{}", rendered);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::interpreter::error::RavenError;
+
+use super::{everywhere, recording, run, ENGINES};
+
+const DEEP: &str = "fn deep(n) { 1 + deep(n + 1) }\ndeep(0)";
+const DOWN: &str = "fn down(n) { if n == 0 { 0 } else { 1 + down(n - 1) } }\n";
+
+/// what `code` prints on each engine with calls nesting at most `depth` deep, or the error it fails with
+fn limited(code: &str, depth: usize) -> Vec<Result<Vec<String>, RavenError>> {
+    everywhere(code, |i| i.set_max_depth(depth)).into_iter()
+        .map(|(result, output)| result.map(|_| output).map_err(|error| error.inner().clone()))
+        .collect()
+}
+
+#[test]
+pub fn tail_calls_do_not_nest(){
+    const CODE: &str = "fn sum(n, total) {\n    if n == 0 { return total }\n    sum(n - 1, total + n)\n}\nprint(sum(1000, 0))";
+    for result in limited(CODE, 10) {
+        assert_eq!(result, Ok(vec!["500500".to_string()]));
+    }
+}
+
+#[test]
+pub fn stack_overflow(){
+    for result in limited(DEEP, 50) {
+        assert_eq!(result, Err(RavenError::StackOverflow));
+    }
+    for result in limited(&format!("{}print(down(40))", DOWN), 50) {
+        assert_eq!(result, Ok(vec!["40".to_string()]));
+    }
+}
+
+#[test]
+pub fn deeper_than_the_native_stack(){
+    // the test threads have a couple of megabytes of stack, far less than this many calls take on the tree walker
+    let walked = limited(&format!("{}print(down(3000))", DOWN), 5000).remove(0);
+    assert_eq!(walked, Ok(vec!["3000".to_string()]));
+    let walked = limited(DEEP, 5000).remove(0);
+    assert_eq!(walked, Err(RavenError::StackOverflow));
+}
+
+#[test]
+pub fn overflow_is_recoverable(){
+    let (mut i, output) = recording();
+    i.set_max_depth(100);
+    for vm in ENGINES {
+        assert!(run(&mut i, DEEP, vm).is_err());
+    }
+    for vm in ENGINES {
+        run(&mut i, &format!("{}print(down(90))", DOWN), vm).unwrap();
+    }
+    assert!(output.borrow().iter().all(|line| line == "90"));
+}
+
+#[test]
+#[cfg(feature = "vm")]
+pub fn compiled_tail_calls(){
+    use crate::parser::parser::ParseString;
+    use crate::vm::{bytecode::Op, compiler::Compiler};
+
+    let code = ParseString("fn f(n) {\n    if n { g(1) } else { g(2) }\n    return g(3)\n}").unwrap();
+    let program = Compiler::compile(&code).unwrap();
+    let calls = program.chunk.functions[0].chunk.code.iter()
+        .filter_map(|op| match op { Op::Call(_) => Some(false), Op::TailCall(_) => Some(true), _ => None })
+        .collect::<Vec<_>>();
+    // the `if` is not the last line, so only `return g(3)` is in tail position
+    assert_eq!(calls, vec![false, false, true]);
+}
@@ -0,0 +1,53 @@
+use crate::repl::{Repl, Reply};
+
+fn output(repl: &mut Repl, line: &str) -> String {
+    match repl.line(line) {
+        Reply::Output(output) => output,
+        Reply::Incomplete => panic!("`{}` should have been complete", line),
+        Reply::Quit => panic!("`{}` should not quit", line),
+    }
+}
+
+#[test]
+pub fn keeps_state_between_lines(){
+    crate::color::disable();
+    for vm in super::ENGINES {
+        let mut repl = Repl::new(vm);
+        assert_eq!(output(&mut repl, "let x = 2"), "");
+        assert_eq!(output(&mut repl, "fn double(n) { n * 2 }"), "");
+        assert_eq!(output(&mut repl, "double(x)"), "4");
+        assert_eq!(output(&mut repl, "[x, \"a\"]"), "[2, \"a\"]");
+        assert!(output(&mut repl, "missing").contains("cannot find variable `missing`"));
+        assert_eq!(output(&mut repl, "x + 1"), "3");
+    }
+}
+
+#[test]
+pub fn waits_for_incomplete_input(){
+    crate::color::disable();
+    let mut repl = Repl::new(false);
+    assert!(matches!(repl.line("fn add(a, b) {"), Reply::Incomplete));
+    assert_eq!(repl.prompt(), ".. ");
+    assert!(matches!(repl.line("    a + b"), Reply::Incomplete));
+    assert_eq!(output(&mut repl, "}"), "");
+    assert_eq!(repl.prompt(), ">> ");
+    assert_eq!(output(&mut repl, "add(1, 2)"), "3");
+
+    assert!(matches!(repl.line("print(1,"), Reply::Incomplete));
+    assert!(output(&mut repl, "").contains("expected"));
+    assert!(output(&mut repl, "1 +* 2").contains("error"));
+}
+
+#[test]
+pub fn meta_commands(){
+    crate::color::disable();
+    let mut repl = Repl::new(false);
+    output(&mut repl, "let b = 1");
+    output(&mut repl, "let a = [b]");
+    assert_eq!(output(&mut repl, ":vars"), "a = [1]\nb = 1");
+    assert_eq!(output(&mut repl, ":ast x += 1"), "x = (x + 1)");
+    assert_eq!(output(&mut repl, ":reset"), "");
+    assert_eq!(output(&mut repl, ":vars"), "");
+    assert!(output(&mut repl, ":nope").contains("unknown command `:nope`"));
+    assert!(matches!(repl.line(":quit"), Reply::Quit));
+}
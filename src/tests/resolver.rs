@@ -0,0 +1,99 @@
+use crate::interpreter::{error::RavenError, interpreter::Scope, resolver::Resolver};
+use crate::parser::parser::{ParseString, Type};
+
+use super::{everywhere, recording};
+
+/// the error each engine gives for `code`, and whether anything was printed before it
+fn rejected(code: &str) -> Vec<(RavenError, bool)> {
+    everywhere(code, |_| {}).into_iter().map(|(result, output)| (result.unwrap_err(), !output.is_empty())).collect()
+}
+
+#[test]
+pub fn undefined_before_running(){
+    for (error, printed) in rejected("print(1)\nprint(2 * missing)") {
+        assert_eq!(error.inner(), &RavenError::UndefinedVariable("missing".to_string()));
+        assert_eq!(error.span().map(|span| span.start), Some(17));
+        assert!(!printed);
+    }
+    // inside functions that are never called, and assignments too
+    for (error, printed) in rejected("print(1)\nfn f(a) {\n    a + b\n}") {
+        assert_eq!(error.inner(), &RavenError::UndefinedVariable("b".to_string()));
+        assert!(!printed);
+    }
+    for (error, _) in rejected("fn f() {\n    let x = 1\n}\nx = 2") {
+        assert_eq!(error.inner(), &RavenError::UndefinedVariable("x".to_string()));
+    }
+    // a loop variable ends with its loop, and a variable with its function
+    for (error, _) in rejected("for i in 0..2 {}\nfn f() { i }") {
+        assert_eq!(error.inner(), &RavenError::UndefinedVariable("i".to_string()));
+    }
+}
+
+#[test]
+pub fn globals_are_late_bound(){
+    // functions may use what the top level declares further down
+    let code = "fn even(n) {\n    if n == 0 { return true }\n    odd(n - 1)\n}\nfn odd(n) {\n    if n == 0 { return false }\n    even(n - 1)\n}\nprint(even(10), odd(7))";
+    for (result, output) in everywhere(code, |_| {}) {
+        result.unwrap();
+        assert_eq!(output, ["true true"]);
+    }
+
+    // and whatever earlier runs and the embedder declared
+    let (mut i, output) = recording();
+    i.run("let x = 1".to_string(), false).unwrap();
+    i.addObject("y", Type::Integer(2));
+    i.run("fn f() { x + y }\nprint(f())".to_string(), false).unwrap();
+    assert_eq!(*output.borrow(), vec!["3"]);
+}
+
+#[test]
+pub fn slots(){
+    let mut code = ParseString("let g = 1\nfn f(a, b) {\n    let c = a\n    for i in [b] {\n        let a = i\n        c = g + a\n    }\n    () => { c }\n}").unwrap();
+    let builtins = Scope::new();
+    for name in ["__list__", "__add__"] {
+        builtins.borrow_mut().declare(name.to_string(), Type::Integer(0).wrap());
+    }
+    Resolver::resolve(&mut code, &builtins).unwrap();
+    let resolved = format!("{:?}", code);
+    for local in [
+        // the parameters come first, then what the body declares
+        r#"Local { name: "c", depth: 0, index: 2 }, value: Local { name: "a", depth: 0, index: 0 }"#,
+        r#"iterable: Call { function: Symbol("__list__"), arguments: [Local { name: "b", depth: 0, index: 1 }]"#,
+        // a loop body is a frame of its own with the loop variable first
+        r#"Local { name: "a", depth: 0, index: 1 }, value: Local { name: "i", depth: 0, index: 0 }"#,
+        r#"variable: Local { name: "c", depth: 1, index: 2 }, value: Call { function: Symbol("__add__"), arguments: [Symbol("g"), Local { name: "a", depth: 0, index: 1 }]"#,
+        r#"code: [Local { name: "c", depth: 1, index: 2 }]"#,
+    ] {
+        assert!(resolved.contains(local), "{} not in {}", local, resolved);
+    }
+    // the top level keeps its names
+    assert!(resolved.contains(r#"VariableDeclaration { variable: Symbol("g")"#));
+}
+
+#[test]
+pub fn declared_in_branches(){
+    let (mut i, output) = recording();
+    i.run("fn f(c) {\n    if c { let y = 1 }\n    y\n}\nprint(f(true))".to_string(), false).unwrap();
+    assert_eq!(*output.borrow(), vec!["1"]);
+    let error = i.run("f(false)".to_string(), false).unwrap_err();
+    assert_eq!(error.inner(), &RavenError::UndefinedVariable("y".to_string()));
+}
+
+#[test]
+pub fn declared_later_in_the_frame(){
+    // functions in a function can call each other, and closures read what is declared after them
+    for code in [
+        "fn outer() {\n    fn a(n) { if n > 0 { b(n - 1) } else { 0 } }\n    fn b(n) { a(n) }\n    a(3)\n}\nprint(outer())",
+        "fn f() {\n    let g = () => { x }\n    let x = 0\n    g()\n}\nprint(f())",
+        "for i in [0] {\n    let g = () => { later }\n    let later = i\n    print(g())\n}",
+    ] {
+        for (result, output) in everywhere(code, |_| {}) {
+            result.unwrap();
+            assert_eq!(output, ["0"]);
+        }
+    }
+    // but until its declaration runs a variable is still the one from outside
+    let (mut i, output) = recording();
+    i.run("let x = 1\nfn f() {\n    let x = x + 1\n    x\n}\nprint(f(), x)".to_string(), false).unwrap();
+    assert_eq!(*output.borrow(), vec!["2 1"]);
+}
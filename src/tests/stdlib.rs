@@ -0,0 +1,44 @@
+use std::fs::read_to_string;
+
+use super::{failure, recording, run, ENGINES};
+use crate::interpreter::error::RavenError;
+use crate::interpreter::interpreter::Interpreter;
+
+#[test]
+pub fn stdlib_script(){
+    let code = read_to_string("src/tests/scripts/stdlib.rv").unwrap();
+    for vm in ENGINES {
+        let (mut i, output) = recording();
+        let result = run(&mut i, &code, vm);
+        if let Err(error) = result {
+            panic!("{}", error.render(&code, "stdlib.rv"));
+        }
+        assert_eq!(*output.borrow(), ["ALL_GOOD"]);
+    }
+}
+
+#[test]
+pub fn assertions(){
+    assert_eq!(failure("assert(1 > 2)").to_string(), "assertion failed");
+    assert_eq!(failure("assert(false, \"expected\", 2)").to_string(), "assertion failed: expected 2");
+    assert_eq!(failure("assert_eq([1, \"a\"], [1, \"b\"])").to_string(), "assertion failed: [1, \"a\"] != [1, \"b\"]");
+    assert_eq!(failure("assert(1)"), RavenError::TypeMismatch { operation: "assert".to_string(), operands: vec!["integer".to_string()] });
+}
+
+#[test]
+pub fn library_errors(){
+    assert_eq!(failure("sqrt(\"4\")").to_string(), "cannot use as a number string");
+    assert_eq!(failure("min([])").to_string(), "cannot take the min of an empty list");
+    assert_eq!(failure("random(3, 3)").to_string(), "cannot pick a random number from the empty range 3..3");
+    assert_eq!(failure("floor(0.0 / 0.0)").to_string(), "cannot floor NaN to an integer");
+    assert_eq!(failure("upper(1)").to_string(), "cannot use as a string integer");
+}
+
+#[test]
+pub fn bare_interpreter(){
+    // without the library the language still works, there is just nothing to call
+    let mut i = Interpreter::new();
+    let value = i.run("let xs = [1, 2]\nxs[1] * 3 == 6 && true".to_string(), false).unwrap().unwrap();
+    assert_eq!(value.borrow().toString(), "true");
+    assert_eq!(i.run("print(1)".to_string(), false).unwrap_err().inner(), &RavenError::UndefinedVariable("print".to_string()));
+}
@@ -0,0 +1,179 @@
+use std::fs::read_to_string;
+
+use super::{everywhere, failure, recording, run, ENGINES};
+use crate::interpreter::convert::Callback;
+use crate::interpreter::error::{RavenError, RavenResult};
+use crate::interpreter::interpreter::Interpreter;
+use crate::parser::parser::{ParseString, Type};
+use crate::parser::span::Span;
+use crate::vm::bytecode::{Op, Operator};
+use crate::vm::compiler::Compiler;
+use crate::vm::vm::RavenVM;
+
+fn same_output(path: &str) {
+    let code = read_to_string(path).unwrap();
+
+    let (mut walker, expected) = recording();
+    walker.run(code.clone(), false).unwrap();
+
+    let (mut vm, actual) = recording();
+    vm.runVM(code, false).unwrap();
+
+    assert!(!expected.borrow().is_empty());
+    assert_eq!(*actual.borrow(), *expected.borrow());
+}
+
+#[test]
+pub fn basics_script(){
+    same_output("src/tests/scripts/basics.rv");
+}
+
+#[test]
+pub fn loops_script(){
+    same_output("src/tests/scripts/loops.rv");
+}
+
+#[test]
+pub fn returns_script(){
+    same_output("src/tests/scripts/returns.rv");
+}
+
+#[test]
+pub fn control_script(){
+    same_output("src/tests/scripts/control.rv");
+}
+
+#[test]
+pub fn lists_script(){
+    same_output("src/tests/scripts/lists.rv");
+}
+
+#[test]
+pub fn maps_script(){
+    same_output("src/tests/scripts/maps.rv");
+}
+
+#[test]
+pub fn for_script(){
+    same_output("src/tests/scripts/for.rv");
+}
+
+#[test]
+pub fn integers_script(){
+    same_output("src/tests/scripts/integers.rv");
+}
+
+#[test]
+pub fn closures_script(){
+    same_output("src/tests/scripts/closures.rv");
+}
+
+#[test]
+pub fn closures_share_their_scope(){
+    let code = "fn counter() {\n let n = 0\n () => { n += 1\n n }\n}\nlet c = counter()\nc()\nc()\nprint(c())";
+    let (mut walker, walked) = recording();
+    walker.run(code.to_string(), false).unwrap();
+    let (mut vm, ran) = recording();
+    vm.runVM(code.to_string(), false).unwrap();
+    assert_eq!(*walked.borrow(), vec!["3".to_string()]);
+    assert_eq!(*ran.borrow(), vec!["3".to_string()]);
+}
+
+#[test]
+pub fn compile_arithmetic(){
+    let program = Compiler::compile(&ParseString("1 + x").unwrap()).unwrap();
+    let chunk = &program.chunk;
+    assert_eq!(chunk.code, vec![Op::Constant(0), Op::Load(0), Op::Operator(Operator::Add), Op::Return]);
+    assert_eq!(chunk.constants, vec![Type::Integer(1)]);
+    assert_eq!(chunk.names, vec!["x".to_string()]);
+}
+
+#[test]
+pub fn operators_on_other_values(){
+    let code = "print(\"a\" + \"b\", [1] == [1], 1 < 2.5, 2 ** 3, 7 % 2, 1 / 2, true && false, 3000000000 + 1 > 3000000000)";
+    for (result, output) in everywhere(code, |_| {}) {
+        result.unwrap();
+        assert_eq!(output, vec!["ab true true 8 1 0 false true".to_string()]);
+    }
+    assert_eq!(failure("1 + true"), failure("__add__(1, true)"));
+    assert_eq!(failure("1 / 0"), failure("__div__(1, 0)"));
+}
+
+#[test]
+pub fn operators_declared_by_the_code(){
+    let code = "fn __add__(a, b) { \"custom\" }\nprint(1 + 2)\nfn f() {\n fn __lt__(a, b) { \"local\" }\n 1 < 2\n}\nprint(f())";
+    for (result, output) in everywhere(code, |_| {}) {
+        result.unwrap();
+        assert_eq!(output, vec!["custom".to_string(), "local".to_string()]);
+    }
+    let host = |i: &mut Interpreter| i.register("__mul__", |x: i64, y: i64| x - y);
+    for (result, output) in everywhere("print(5 * 2)", host) {
+        result.unwrap();
+        assert_eq!(output, vec!["3".to_string()]);
+    }
+    // compiled before the name was given something else
+    for vm in ENGINES {
+        let (mut i, output) = recording();
+        run(&mut i, "fn sum() { 1 + 2 }\nprint(sum())", vm).unwrap();
+        run(&mut i, "__add__ = (a, b) => { \"replaced\" }\nprint(sum())", vm).unwrap();
+        assert_eq!(*output.borrow(), vec!["3".to_string(), "replaced".to_string()]);
+    }
+}
+
+#[test]
+pub fn locals_take_slots(){
+    let code = "fn f(a) {\n let b = a * 2\n b += 1\n b\n}\nprint(f(3))";
+    let (mut i, output) = recording();
+    i.runVM(code.to_string(), false).unwrap();
+    assert_eq!(*output.borrow(), vec!["7".to_string()]);
+}
+
+#[test]
+pub fn compiled_functions_called_from_rust(){
+    // each call runs on a vm the one before handed back
+    let (mut i, _) = recording();
+    i.runVM("fn twice(x) { x * 2 }".to_string(), false).unwrap();
+    for n in 0..3i64 {
+        assert_eq!(i.call("twice", (n,)).unwrap().get::<i64>().unwrap(), n * 2);
+    }
+}
+
+#[test]
+pub fn errors_inside_functions_located(){
+    const CODE: &str = "fn f() {\n  1\n}\nfn k() {\n  if false { let z = 1 }\n  z\n}\nk()";
+    let (mut i, _) = recording();
+    let error = i.runVM(CODE.to_string(), false).unwrap_err();
+    assert_eq!(*error.inner(), RavenError::UndefinedVariable("z".to_string()));
+    assert_eq!(Span::lineCol(CODE, error.span().unwrap().start), (4, 1));
+}
+
+#[test]
+pub fn vms_nested_through_builtins(){
+    // each call back into compiled code from `nest` runs on a vm of its own
+    let (mut i, output) = recording();
+    i.register("nest", |function: Callback, n: i64| -> RavenResult<i64> { function.call((n,)) });
+    i.set_max_depth(50);
+    i.runVM("fn down(n) { if n == 0 { 0 } else { 1 + nest(down, n - 1) } }\nprint(down(20))".to_string(), false).unwrap();
+    assert!(RavenVM::spares() <= 4);
+
+    let error = i.runVM("print(1 + nest(down, -1))".to_string(), false).unwrap_err();
+    assert_eq!(*error.inner(), RavenError::StackOverflow);
+    i.runVM("print(down(3))".to_string(), false).unwrap();
+    assert_eq!(*output.borrow(), vec!["20".to_string(), "3".to_string()]);
+    assert!(RavenVM::spares() <= 4);
+}
+
+#[test]
+pub fn last_value(){
+    let (mut i, _) = recording();
+    let result = i.runVM("let x = 2\nwhile x < 100 { x *= x }\nx".to_string(), false).unwrap().unwrap();
+    assert_eq!(*result.borrow(), Type::Integer(256));
+
+    let result = i.runVM("let y = 1".to_string(), false).unwrap();
+    assert_eq!(result, None);
+}
+
+#[test]
+pub fn recursion_script(){
+    same_output("src/tests/scripts/recursion.rv");
+}
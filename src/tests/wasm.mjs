@@ -0,0 +1,27 @@
+// checks the built module under node, run it with `make test-wasm`
+import assert from "node:assert/strict";
+import { readFile } from "node:fs/promises";
+
+import { load } from "../../docs/raven.js";
+
+const raven = await load(await readFile(new URL("../../docs/raven.wasm", import.meta.url)));
+
+assert.deepEqual(raven.run('print("hello", 1)\nlet x = [1, 2]\nx[1] * 3'), { stdout: "hello 1\n", result: "6", error: null });
+assert.deepEqual(raven.run('"ü" + "\\n"'), { stdout: "", result: '"ü\n"', error: null });
+assert.deepEqual(raven.run("let x = 1"), { stdout: "", result: null, error: null });
+
+const failed = raven.run("print(1)\n1 / 0");
+assert.equal(failed.stdout, "1\n");
+assert.equal(failed.result, null);
+assert.match(failed.error, /division by zero\n --> <input>:2:/);
+
+const undefined = raven.run("print(1)\nmissing + 1");
+assert.equal(undefined.stdout, "");
+assert.match(undefined.error, /cannot find variable `missing`\n --> <input>:2:/);
+
+const roll = raven.run("random(1, 7)");
+assert.ok(Number(roll.result) >= 1 && Number(roll.result) < 7);
+
+// every run starts over
+assert.match(raven.run("x").error, /cannot find variable `x`/);
+console.log("wasm ok");
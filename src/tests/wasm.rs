@@ -0,0 +1,41 @@
+use crate::interpreter::depth::WASM_MAX_DEPTH;
+use crate::wasm::wasm::{run, Output};
+
+#[test]
+pub fn output(){
+    let output = run("print(\"hello\", 1)\nlet x = [1, 2]\nx[1] * 3");
+    assert_eq!(output, Output { stdout: "hello 1\n".to_string(), result: Some("6".to_string()), error: None });
+    assert_eq!(run("let x = 1").result, None);
+    assert_eq!(run("\"a\" + \"b\"").result.as_deref(), Some("\"ab\""));
+}
+
+#[test]
+pub fn errors(){
+    let output = run("print(1)\n1 / 0");
+    assert_eq!(output.stdout, "1\n");
+    assert_eq!(output.result, None);
+    assert!(output.error.unwrap().contains("division by zero"));
+    // undefined variables are found before anything runs
+    let output = run("print(1)\nmissing + 1");
+    assert_eq!(output.stdout, "");
+    assert!(output.error.unwrap().contains("missing"));
+    // every run starts over
+    assert!(run("x").error.is_some());
+}
+
+#[test]
+pub fn recursion_depth(){
+    // deep recursion fails with an error before the module runs out of stack, which it cannot grow
+    const CODE: &str = "fn down(n) { if n == 0 { 0 } else { 1 + down(n - 1) } }\n";
+    assert_eq!(run(&format!("{}down({})", CODE, WASM_MAX_DEPTH - 1)).result, Some((WASM_MAX_DEPTH - 1).to_string()));
+    let output = run(&format!("{}down({})", CODE, WASM_MAX_DEPTH));
+    assert!(output.error.unwrap().contains("stack overflow"));
+    // tail calls take no room
+    assert_eq!(run("fn count(n) { if n == 0 { 0 } else { count(n - 1) } }\ncount(100000)").result.as_deref(), Some("0"));
+}
+
+#[test]
+pub fn json(){
+    let output = Output { stdout: "a \"b\"\n\\\t\u{1}".to_string(), result: None, error: Some("ü".to_string()) };
+    assert_eq!(output.toJson(), r#"{"stdout":"a \"b\"\n\\\t\u0001","result":null,"error":"ü"}"#);
+}
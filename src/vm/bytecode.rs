@@ -0,0 +1,147 @@
+use std::rc::Rc;
+
+use crate::parser::{parser::Type, span::Span};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op{
+    /// push a fresh copy of `constants[i]`
+    Constant(u32),
+    /// push the "no value" marker produced by declarations, assignments and empty blocks
+    Void,
+    Pop,
+    Dup,
+    /// push the value bound to `names[i]`
+    Load(u32),
+    /// pop a value and declare `names[i]` in the current scope
+    Declare(u32),
+    /// pop a value and assign it to the existing `names[i]`
+    Assign(u32),
+    /// push the value of the variable in `locals[i]`
+    LoadLocal(u32),
+    /// pop a value into the slot of `locals[i]` in the current frame
+    DeclareLocal(u32),
+    /// pop a value and assign it to the existing variable in `locals[i]`
+    AssignLocal(u32),
+    /// push a function built from `functions[i]` closing over the current scope
+    Closure(u32),
+    /// pop the callee and then `n` arguments, push the result
+    Call(u8),
+    /// pop two operands and push what the operator gives for them
+    Operator(Operator),
+    /// a `Call` whose result the function returns, the callee runs in place of the caller's frame
+    TailCall(u8),
+    Jump(u32),
+    /// drop values until only `n` are left above the frame's base, used to leave a loop early
+    Truncate(u32),
+    /// pop a bool and jump when it is false
+    JumpIfFalse(u32),
+    /// with a loop's iterable and position under its value, advance the position and run the following
+    /// code in a frame of its own with the next item in its first slot, or jump when there are no items left
+    Next(u32),
+    /// go back to the scope that was current before the matching `Next`
+    ExitScope,
+    /// drop the `n` values under the top one
+    Slide(u32),
+    Return,
+}
+
+/// the builtins the syntax lowers binary operators to. the vm works out numbers, integers and bools
+/// itself, and calls what the name holds for other operands or once the code declared its own
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator{
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+    Modulo,
+    Equals,
+    NotEquals,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    And,
+    Or,
+}
+
+impl Operator{
+    const ALL: [Operator; 14] = [
+        Operator::Add, Operator::Subtract, Operator::Multiply, Operator::Divide, Operator::Power, Operator::Modulo, Operator::Equals,
+        Operator::NotEquals, Operator::Less, Operator::LessOrEqual, Operator::Greater, Operator::GreaterOrEqual, Operator::And, Operator::Or,
+    ];
+
+    pub fn builtin(self) -> &'static str{
+        match self {
+            Operator::Add => "__add__",
+            Operator::Subtract => "__sub__",
+            Operator::Multiply => "__mul__",
+            Operator::Divide => "__div__",
+            Operator::Power => "__pow__",
+            Operator::Modulo => "__mod__",
+            Operator::Equals => "__eq__",
+            Operator::NotEquals => "__ne__",
+            Operator::Less => "__lt__",
+            Operator::LessOrEqual => "__le__",
+            Operator::Greater => "__gt__",
+            Operator::GreaterOrEqual => "__ge__",
+            Operator::And => "__and__",
+            Operator::Or => "__or__",
+        }
+    }
+
+    /// the operator whose builtin is called `name`
+    pub fn named(name: &str) -> Option<Operator>{
+        Self::ALL.into_iter().find(|operator| operator.builtin() == name)
+    }
+}
+
+/// a variable the `Resolver` gave a slot, in the frame `depth` frames up from the current one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Local{
+    pub name: String,
+    pub depth: usize,
+    pub index: usize,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Chunk{
+    pub code: Vec<Op>,
+    /// span of the node each instruction was compiled from
+    pub spans: Vec<Span>,
+    pub constants: Vec<Type>,
+    pub names: Vec<String>,
+    pub locals: Vec<Local>,
+    pub functions: Vec<Rc<Prototype>>,
+}
+
+/// compiled body of a function (or of a whole file) plus the parameters it binds
+#[derive(Debug, PartialEq)]
+pub struct Prototype{
+    pub name: String,
+    pub parameters: Vec<String>,
+    pub chunk: Chunk,
+}
+
+impl Chunk{
+    pub fn disassemble(&self) -> String{
+        let mut out = String::new();
+        for (i, op) in self.code.iter().enumerate(){
+            let detail = match op {
+                Op::Constant(c) => format!(" ({})", self.constants[*c as usize].toString()),
+                Op::Load(n) | Op::Declare(n) | Op::Assign(n) => format!(" ({})", self.names[*n as usize]),
+                Op::LoadLocal(l) | Op::DeclareLocal(l) | Op::AssignLocal(l) => {
+                    let local = &self.locals[*l as usize];
+                    format!(" ({} at {}, {})", local.name, local.depth, local.index)
+                },
+                Op::Closure(f) => format!(" (fn {})", self.functions[*f as usize].name),
+                _ => String::new()
+            };
+            out += &format!("{:04} {:?}{}\n", i, op, detail);
+        }
+        for function in &self.functions{
+            out += &format!("\nfn {}({}):\n{}", function.name, function.parameters.join(", "), function.chunk.disassemble());
+        }
+        out
+    }
+}
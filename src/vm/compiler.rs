@@ -0,0 +1,308 @@
+use std::rc::Rc;
+
+use crate::parser::{parser::Type, span::Span};
+use crate::interpreter::error::{RavenError, RavenResult};
+use super::bytecode::{Chunk, Local, Op, Operator, Prototype};
+
+struct Loop{
+    /// where `continue` jumps to
+    start: u32,
+    /// stack height the loop's value sits on
+    height: u32,
+    breaks: Vec<usize>
+}
+
+/// lowers parsed `Type` trees into bytecode for the `RavenVM`
+#[derive(Default)]
+pub struct Compiler{
+    chunk: Chunk,
+    span: Span,
+    /// whether `return` has a function to leave
+    function: bool,
+    loops: Vec<Loop>,
+    /// how many values the code emitted so far leaves on the stack
+    depth: u32
+}
+
+impl Compiler{
+    pub fn compile(code: &[Type]) -> RavenResult<Rc<Prototype>>{
+        let mut compiler = Compiler::default();
+        compiler.block(code.iter())?;
+        compiler.emit(Op::Return);
+        Ok(Rc::new(Prototype { name: "<main>".to_string(), parameters: vec![], chunk: compiler.chunk }))
+    }
+
+    /// `span` is where the function is declared, what the lines of its body without a span of their own report
+    fn function(name: String, parameters: Vec<String>, code: &[Box<Type>], span: Span) -> RavenResult<Prototype>{
+        let mut compiler = Compiler { function: true, span, ..Compiler::default() };
+        compiler.block(code.iter().map(|line| &**line))?;
+        compiler.emit(Op::Return);
+        compiler.tailCalls();
+        Ok(Prototype { name, parameters, chunk: compiler.chunk })
+    }
+
+    /// turns the calls whose result the function returns as it is into `Op::TailCall`
+    fn tailCalls(&mut self){
+        let code = &self.chunk.code;
+        let tail = |mut at: usize| {
+            // what runs between the call and the return only moves between the scopes and values the
+            // returning frame drops anyway
+            for _ in 0..code.len() {
+                match code[at] {
+                    Op::Return => return true,
+                    Op::Jump(target) => at = target as usize,
+                    Op::ExitScope | Op::Slide(_) => at += 1,
+                    _ => return false,
+                }
+            }
+            false
+        };
+        let calls = (0..code.len()).filter(|&at| matches!(code[at], Op::Call(_)) && tail(at + 1)).collect::<Vec<_>>();
+        for at in calls {
+            if let Op::Call(count) = self.chunk.code[at] {
+                self.chunk.code[at] = Op::TailCall(count);
+            }
+        }
+    }
+
+    /// every block leaves exactly one value on the stack: its last line, or void
+    fn block<'a>(&mut self, code: impl Iterator<Item = &'a Type>) -> RavenResult<()>{
+        let mut empty = true;
+        for line in code.filter(|line| !matches!(line, Type::Blank)){
+            if !empty {
+                self.emit(Op::Pop);
+            }
+            self.expression(line)?;
+            empty = false;
+        }
+        if empty {
+            self.emit(Op::Void);
+        }
+        Ok(())
+    }
+
+    fn expression(&mut self, node: &Type) -> RavenResult<()>{
+        let outer = self.span;
+        self.span = node.span().unwrap_or(outer);
+        let result = self.node(node);
+        self.span = outer;
+        result
+    }
+
+    fn node(&mut self, node: &Type) -> RavenResult<()>{
+        match node {
+            Type::Call { function, arguments, .. } => {
+                if arguments.len() > u8::MAX as usize {
+                    let error = RavenError::Runtime(format!("cannot call a function with more than {} arguments", u8::MAX));
+                    return Err(error.at(self.span))
+                }
+                for argument in arguments{
+                    self.expression(argument)?;
+                }
+                match &**function {
+                    // what `a + b` and the like lower to. a local of the name is never the builtin, a global one is
+                    // checked when the operator runs
+                    Type::Symbol(name) if arguments.len() == 2 && Operator::named(name).is_some() => {
+                        self.emit(Op::Operator(Operator::named(name).unwrap()));
+                        return Ok(())
+                    },
+                    callee @ (Type::Symbol(_) | Type::Local { .. }) => self.load(callee)?,
+                    callee => self.expression(callee)?,
+                }
+                self.emit(Op::Call(arguments.len() as u8));
+            },
+            Type::VariableDeclaration { variable, value, .. } => {
+                self.expression(value)?;
+                self.store(variable, true)?;
+                self.emit(Op::Void);
+            },
+            Type::Assignment { variable, value, .. } => {
+                self.expression(value)?;
+                self.store(variable, false)?;
+                self.emit(Op::Void);
+            },
+            Type::CreateFunction { name, code, parameters, .. } => {
+                let function = Compiler::function(Self::Symbol(name)?.to_string(), parameters.clone(), code, self.span).map_err(|error| error.at(self.span))?;
+                self.chunk.functions.push(Rc::new(function));
+                self.emit(Op::Closure(self.chunk.functions.len() as u32 - 1));
+                self.emit(Op::Dup);
+                self.store(name, true)?;
+            },
+            Type::Conditional { condition, then, otherwise, .. } => {
+                self.expression(condition)?;
+                let to_otherwise = self.emit(Op::JumpIfFalse(0));
+                let height = self.depth;
+                self.block(then.iter())?;
+                let to_end = self.emit(Op::Jump(0));
+                self.patch(to_otherwise);
+                self.depth = height;
+                match otherwise {
+                    Some(otherwise) => self.block(otherwise.iter())?,
+                    None => { self.emit(Op::Void); }
+                }
+                self.patch(to_end);
+            },
+            Type::While { condition, code, .. } => {
+                // the loop keeps the value of its last iteration on the stack while it runs
+                let height = self.depth;
+                self.emit(Op::Void);
+                let start = self.chunk.code.len() as u32;
+                self.expression(condition)?;
+                let to_end = self.emit(Op::JumpIfFalse(0));
+                self.emit(Op::Pop);
+                self.loops.push(Loop { start, height, breaks: vec![] });
+                self.block(code.iter())?;
+                let finished = self.loops.pop().unwrap();
+                self.emit(Op::Jump(start));
+                self.patch(to_end);
+                for jump in finished.breaks {
+                    self.patch(jump);
+                }
+                self.depth = height + 1;
+            },
+            Type::For { iterable, code, .. } => {
+                // the iterable and the position in it stay under the loop's value while it runs
+                let height = self.depth;
+                self.expression(iterable)?;
+                self.chunk.constants.push(Type::Integer(0));
+                self.emit(Op::Constant(self.chunk.constants.len() as u32 - 1));
+                self.emit(Op::Void);
+                // every iteration but the first starts by leaving the scope of the one before
+                let to_first = self.emit(Op::Jump(0));
+                let start = self.chunk.code.len() as u32;
+                self.emit(Op::ExitScope);
+                self.patch(to_first);
+                let to_end = self.emit(Op::Next(0));
+                self.emit(Op::Pop);
+                self.loops.push(Loop { start, height: height + 2, breaks: vec![] });
+                self.block(code.iter())?;
+                let finished = self.loops.pop().unwrap();
+                self.emit(Op::Jump(start));
+                for jump in finished.breaks {
+                    self.patch(jump);
+                }
+                self.emit(Op::ExitScope);
+                self.patch(to_end);
+                self.emit(Op::Slide(2));
+                self.depth = height + 1;
+            },
+            Type::Break { .. } | Type::Continue { .. } => {
+                let keyword = if let Type::Break { .. } = node { "break" } else { "continue" };
+                let Some(innermost) = self.loops.last() else {
+                    let error = RavenError::Misplaced { keyword: keyword.to_string(), context: "loop".to_string() };
+                    return Err(error.at(self.span))
+                };
+                let (start, height) = (innermost.start, innermost.height);
+                let depth = self.depth;
+                self.emit(Op::Truncate(height));
+                self.emit(Op::Void);
+                if keyword == "break" {
+                    let jump = self.emit(Op::Jump(0));
+                    self.loops.last_mut().unwrap().breaks.push(jump);
+                } else {
+                    self.emit(Op::Jump(start));
+                }
+                // never falls through, but the enclosing block still expects a value
+                self.depth = depth + 1;
+            },
+            Type::Invocation { code } => self.block(code.iter())?,
+            Type::Return { value, .. } => {
+                if !self.function {
+                    let error = RavenError::Misplaced { keyword: "return".to_string(), context: "function".to_string() };
+                    return Err(error.at(self.span))
+                }
+                let depth = self.depth;
+                match value {
+                    Some(value) => self.expression(value)?,
+                    None => { self.emit(Op::Void); }
+                }
+                self.emit(Op::Return);
+                self.depth = depth + 1;
+            },
+            Type::Symbol(_) | Type::Local { .. } => self.load(node)?,
+            node => {
+                self.chunk.constants.push(node.clone());
+                self.emit(Op::Constant(self.chunk.constants.len() as u32 - 1));
+            }
+        }
+        Ok(())
+    }
+
+    fn emit(&mut self, op: Op) -> usize{
+        self.depth = match op {
+            Op::Constant(_) | Op::Void | Op::Dup | Op::Load(_) | Op::LoadLocal(_) | Op::Closure(_) => self.depth + 1,
+            Op::Pop | Op::Declare(_) | Op::Assign(_) | Op::DeclareLocal(_) | Op::AssignLocal(_) | Op::Operator(_)
+                | Op::JumpIfFalse(_) | Op::Return => self.depth - 1,
+            Op::Call(count) | Op::TailCall(count) => self.depth - count as u32,
+            Op::Truncate(height) => height,
+            Op::Slide(count) => self.depth - count,
+            Op::Jump(_) | Op::Next(_) | Op::ExitScope => self.depth,
+        };
+        self.chunk.code.push(op);
+        self.chunk.spans.push(self.span);
+        self.chunk.code.len() - 1
+    }
+
+    /// points the jump at `at` to the next instruction to be emitted
+    fn patch(&mut self, at: usize){
+        let target = self.chunk.code.len() as u32;
+        match &mut self.chunk.code[at] {
+            Op::Jump(to) | Op::JumpIfFalse(to) | Op::Next(to) => *to = target,
+            op => panic!("cannot patch non-jump instruction {:?}", op)
+        }
+    }
+
+    /// pushes the value of a variable, from its slot when the resolver gave it one and by name otherwise
+    fn load(&mut self, node: &Type) -> RavenResult<()>{
+        let op = match node {
+            Type::Local { name, depth, index } => Op::LoadLocal(self.local(name, *depth, *index)),
+            node => Op::Load(self.name(Self::Symbol(node)?)),
+        };
+        self.emit(op);
+        Ok(())
+    }
+
+    /// pops a value into the variable `node`, declaring it or assigning to it
+    fn store(&mut self, node: &Type, declare: bool) -> RavenResult<()>{
+        let op = match node {
+            Type::Local { name, depth, index } => {
+                let local = self.local(name, *depth, *index);
+                if declare { Op::DeclareLocal(local) } else { Op::AssignLocal(local) }
+            },
+            node => {
+                let name = self.name(Self::Symbol(node)?);
+                if declare { Op::Declare(name) } else { Op::Assign(name) }
+            },
+        };
+        self.emit(op);
+        Ok(())
+    }
+
+    fn local(&mut self, name: &str, depth: usize, index: usize) -> u32{
+        let local = Local { name: name.to_string(), depth, index };
+        match self.chunk.locals.iter().position(|l| *l == local) {
+            Some(index) => index as u32,
+            None => {
+                self.chunk.locals.push(local);
+                self.chunk.locals.len() as u32 - 1
+            }
+        }
+    }
+
+    fn name(&mut self, name: &str) -> u32{
+        match self.chunk.names.iter().position(|n| n == name) {
+            Some(index) => index as u32,
+            None => {
+                self.chunk.names.push(name.to_string());
+                self.chunk.names.len() as u32 - 1
+            }
+        }
+    }
+
+    fn Symbol(node: &Type) -> RavenResult<&str>{
+        if let Type::Symbol(symbol) | Type::Local { name: symbol, .. } = node{
+            return Ok(symbol)
+        }
+        Err(RavenError::NotCallable(node.toString()))
+    }
+}
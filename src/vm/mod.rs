@@ -1,10 +1,3 @@
-struct Stack{
-    memory: [u64; 10000]    
-}
-struct Heap{
-    
-}
-
-struct RavenVM{
-    
-}
\ No newline at end of file
+pub mod bytecode;
+pub mod compiler;
+pub mod vm;
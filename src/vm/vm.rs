@@ -0,0 +1,467 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use crate::interpreter::interpreter::{FunctionTypes, Object, RefScope, Scope};
+use crate::interpreter::depth::Call;
+use crate::interpreter::error::{RavenError, RavenResult};
+use crate::interpreter::stdlib;
+use crate::parser::func::{equal, numeric};
+use crate::parser::parser::Type;
+use super::bytecode::{Op, Operator, Prototype};
+
+const STACK_SIZE: usize = 10000;
+/// how many finished vms a thread keeps for later runs. a vm only waits on another one when a builtin
+/// calls back into compiled code, and each one holds a whole stack
+const SPARES: usize = 4;
+
+// every stack word is a tag in the high half and a payload in the low half.
+// numbers, bools and integers that fit in 32 bits live inline, everything else is a handle into the `Heap`.
+const TAG_NUMBER: u64 = 0;
+const TAG_BOOL: u64 = 1;
+const TAG_HEAP: u64 = 2;
+const TAG_VOID: u64 = 3;
+const TAG_INTEGER: u64 = 4;
+
+#[inline(always)]
+fn tag(word: u64) -> u64{
+    word >> 32
+}
+
+#[inline(always)]
+fn payload(word: u64) -> u32{
+    word as u32
+}
+
+#[inline(always)]
+fn word(tag: u64, payload: u32) -> u64{
+    (tag << 32) | payload as u64
+}
+
+pub struct Stack{
+    memory: [u64; STACK_SIZE],
+    top: usize
+}
+impl Stack{
+    fn new() -> Box<Stack>{
+        Box::new(Stack { memory: [0; STACK_SIZE], top: 0 })
+    }
+    fn push(&mut self, word: u64) -> RavenResult<()>{
+        if self.top == STACK_SIZE {
+            return Err(RavenError::StackOverflow)
+        }
+        self.memory[self.top] = word;
+        self.top += 1;
+        Ok(())
+    }
+    fn pop(&mut self) -> u64{
+        self.top -= 1;
+        self.memory[self.top]
+    }
+    fn peek(&self) -> u64{
+        self.memory[self.top - 1]
+    }
+    /// the word `depth` places below the top, 0 being the top itself
+    fn below(&mut self, depth: usize) -> &mut u64{
+        &mut self.memory[self.top - 1 - depth]
+    }
+}
+
+/// owns the objects referenced from the stack. a handle is freed as soon as the
+/// word pointing at it is popped, so every live handle is on the stack exactly once
+#[derive(Default)]
+pub struct Heap{
+    objects: Vec<Option<Object>>,
+    free: Vec<u32>
+}
+impl Heap{
+    fn alloc(&mut self, object: Object) -> u32{
+        match self.free.pop() {
+            Some(handle) => {
+                self.objects[handle as usize] = Some(object);
+                handle
+            },
+            None => {
+                self.objects.push(Some(object));
+                self.objects.len() as u32 - 1
+            }
+        }
+    }
+    fn take(&mut self, handle: u32) -> Object{
+        self.free.push(handle);
+        self.objects[handle as usize].take().unwrap_or_else(|| panic!("dangling heap handle {}", handle))
+    }
+    fn get(&self, handle: u32) -> Object{
+        self.objects[handle as usize].clone().unwrap_or_else(|| panic!("dangling heap handle {}", handle))
+    }
+}
+
+struct Frame{
+    prototype: Rc<Prototype>,
+    ip: usize,
+    scope: RefScope,
+    /// height of the stack when the frame was entered
+    base: usize,
+    /// counts the frame towards the call depth, the frame `run` starts with is counted by its caller
+    call: Option<Call>,
+}
+
+pub struct RavenVM{
+    stack: Box<Stack>,
+    heap: Heap,
+    frames: Vec<Frame>
+}
+
+thread_local!{
+    /// the vms of finished runs. a compiled function called from a builtin runs on one of these rather than
+    /// on a new vm with a stack of its own to set up
+    static SPARE: RefCell<Vec<RavenVM>> = const { RefCell::new(Vec::new()) };
+}
+
+impl Default for RavenVM{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RavenVM{
+    pub fn new() -> RavenVM{
+        RavenVM { stack: Stack::new(), heap: Heap::default(), frames: vec![] }
+    }
+
+    /// runs `prototype` in `scope` on a vm this thread is done with, or a new one when every vm is busy
+    pub fn reuse(prototype: Rc<Prototype>, scope: RefScope) -> RavenResult<Option<Object>>{
+        let mut vm = SPARE.with(|spare| spare.borrow_mut().pop()).unwrap_or_default();
+        let result = vm.run(prototype, scope);
+        SPARE.with(|spare| {
+            let mut spare = spare.borrow_mut();
+            if spare.len() < SPARES {
+                spare.push(vm);
+            }
+        });
+        result
+    }
+
+    /// how many vms the thread keeps for `reuse` right now
+    #[cfg(test)]
+    pub(crate) fn spares() -> usize{
+        SPARE.with(|spare| spare.borrow().len())
+    }
+
+    /// runs `prototype` in `scope` and returns the value of its last line
+    pub fn run(&mut self, prototype: Rc<Prototype>, scope: RefScope) -> RavenResult<Option<Object>>{
+        let (depth, base) = (self.frames.len(), self.stack.top);
+        self.frames.push(Frame { prototype, ip: 0, scope, base, call: None });
+        let result = self.execute(depth).map_err(|error| {
+            let frame = self.frame();
+            error.at(frame.prototype.chunk.spans[frame.ip - 1])
+        });
+        if result.is_err() {
+            // unwind whatever the failed run left behind, and only that, so the vm can be reused
+            self.frames.truncate(depth);
+            while self.stack.top > base {
+                self.discard();
+            }
+        }
+        result
+    }
+
+    fn execute(&mut self, depth: usize) -> RavenResult<Option<Object>>{
+        while self.frames.len() > depth {
+            let frame = self.frames.last_mut().unwrap();
+            let op = frame.prototype.chunk.code[frame.ip];
+            frame.ip += 1;
+
+            match op {
+                Op::Constant(index) => {
+                    let constant = self.frame().prototype.chunk.constants[index as usize].clone();
+                    self.push(Some(constant.wrap()))?;
+                },
+                Op::Void => self.stack.push(word(TAG_VOID, 0))?,
+                Op::Pop => self.discard(),
+                Op::Dup => {
+                    let top = self.stack.peek();
+                    if tag(top) == TAG_HEAP {
+                        let object = self.heap.get(payload(top));
+                        self.push(Some(object))?;
+                    } else {
+                        self.stack.push(top)?;
+                    }
+                },
+                Op::Next(target) => {
+                    // the stack holds the iterable, the position in it, then the loop's value
+                    let position = payload(*self.stack.below(1)) as usize;
+                    let iterable = *self.stack.below(2);
+                    if tag(iterable) != TAG_HEAP || position == 0 {
+                        // the first step swaps what the loop goes over for its `Type::Iterable`
+                        *self.stack.below(2) = word(TAG_VOID, 0);
+                        let iterable = self.release(iterable).ok_or_else(|| RavenError::VoidValue("a loop's iterable".to_string()))?;
+                        *self.stack.below(2) = word(TAG_HEAP, self.heap.alloc(Type::Iterable(iterable)?));
+                    }
+                    let iterable = self.heap.objects[payload(*self.stack.below(2)) as usize].as_ref().expect("a loop's iterable stays on the heap");
+                    let frame = self.frames.last_mut().unwrap();
+                    match Type::Nth(iterable, position)? {
+                        Some(item) => {
+                            *self.stack.below(1) = word(TAG_INTEGER, position as u32 + 1);
+                            frame.scope = Scope::frame(frame.scope.clone(), vec![item]);
+                        },
+                        None => frame.ip = target as usize,
+                    }
+                },
+                Op::ExitScope => {
+                    let frame = self.frames.last_mut().unwrap();
+                    let parent = frame.scope.borrow().parent().expect("ExitScope without a matching Next");
+                    frame.scope = parent;
+                },
+                Op::Slide(count) => {
+                    let top = self.stack.pop();
+                    for _ in 0..count {
+                        self.discard();
+                    }
+                    self.stack.push(top)?;
+                },
+                Op::Load(name) => {
+                    let frame = self.frame();
+                    let value = frame.scope.borrow().get(&frame.prototype.chunk.names[name as usize])?.get();
+                    self.push(Some(value))?;
+                },
+                Op::Declare(name) => {
+                    let name = self.frame().prototype.chunk.names[name as usize].clone();
+                    let value = self.pop().ok_or_else(|| RavenError::VoidValue("a variable's value".to_string()))?;
+                    self.frame().scope.borrow_mut().declare(name, value);
+                },
+                Op::Assign(name) => {
+                    let name = self.frame().prototype.chunk.names[name as usize].clone();
+                    let value = self.pop().ok_or_else(|| RavenError::VoidValue("a variable's value".to_string()))?;
+                    self.frame().scope.borrow_mut().assign(name, value)?;
+                },
+                Op::LoadLocal(local) => {
+                    let frame = self.frame();
+                    let local = &frame.prototype.chunk.locals[local as usize];
+                    let value = frame.scope.borrow().local(local.depth, local.index).ok_or_else(|| RavenError::UndefinedVariable(local.name.clone()))?;
+                    self.push(Some(value))?;
+                },
+                Op::DeclareLocal(local) => {
+                    let value = self.pop().ok_or_else(|| RavenError::VoidValue("a variable's value".to_string()))?;
+                    let frame = self.frame();
+                    frame.scope.borrow_mut().declareLocal(frame.prototype.chunk.locals[local as usize].index, value);
+                },
+                Op::AssignLocal(local) => {
+                    let value = self.pop().ok_or_else(|| RavenError::VoidValue("a variable's value".to_string()))?;
+                    let frame = self.frame();
+                    let local = &frame.prototype.chunk.locals[local as usize];
+                    if !frame.scope.borrow_mut().assignLocal(local.depth, local.index, value) {
+                        return Err(RavenError::UndefinedVariable(local.name.clone()))
+                    }
+                },
+                Op::Operator(operator) => self.operate(operator)?,
+                Op::Closure(index) => {
+                    let frame = self.frame();
+                    let prototype = frame.prototype.chunk.functions[index as usize].clone();
+                    let parameters = prototype.parameters.clone();
+                    let function = FunctionTypes::Compiled { prototype, scope: frame.scope.clone(), parameters, bound: vec![] };
+                    self.push(Some(Type::Function(function).wrap()))?;
+                },
+                Op::Call(count) => self.call(count as usize, false)?,
+                Op::TailCall(count) => self.call(count as usize, true)?,
+                Op::Jump(target) => self.frames.last_mut().unwrap().ip = target as usize,
+                Op::Truncate(height) => {
+                    let height = self.frame().base + height as usize;
+                    while self.stack.top > height {
+                        self.discard();
+                    }
+                },
+                Op::JumpIfFalse(target) => {
+                    let condition = self.stack.pop();
+                    if tag(condition) != TAG_BOOL {
+                        return Err(match self.release(condition) {
+                            Some(value) => RavenError::mismatch("branch on", &[&value.borrow()]),
+                            None => RavenError::VoidValue("a condition".to_string()),
+                        })
+                    }
+                    if payload(condition) == 0 {
+                        self.frames.last_mut().unwrap().ip = target as usize;
+                    }
+                },
+                Op::Return => {
+                    // a return from inside a loop leaves the loop's values behind
+                    let result = self.stack.pop();
+                    let frame = self.frames.pop().unwrap();
+                    while self.stack.top > frame.base {
+                        self.discard();
+                    }
+                    self.stack.push(result)?;
+                },
+            }
+        }
+
+        Ok(self.pop())
+    }
+
+    #[inline(always)]
+    fn frame(&self) -> &Frame{
+        self.frames.last().unwrap()
+    }
+
+    /// a tail call to a compiled function replaces the caller's frame, any other one leaves its result
+    /// for the `Return` after it
+    fn call(&mut self, count: usize, tail: bool) -> RavenResult<()>{
+        let functionObject = self.pop().ok_or_else(|| RavenError::NotCallable("void".to_string()))?;
+        let mut arguments = Vec::with_capacity(count);
+        for _ in 0..count {
+            arguments.push(self.pop());
+        }
+        let arguments = arguments.into_iter().rev()
+            .map(|argument| argument.ok_or_else(|| RavenError::VoidValue("an argument".to_string())))
+            .collect::<RavenResult<Vec<_>>>()?;
+
+        let function = match &*functionObject.borrow() {
+            Type::Function(function) => function.clone(),
+            other => return Err(RavenError::NotCallable(self.calleeName().unwrap_or_else(|| other.toString()))),
+        };
+        match function {
+            // compiled functions get a frame of their own instead of recursing
+            FunctionTypes::Compiled { prototype, scope: captured, parameters, bound } if arguments.len() == parameters.len() => {
+                // the parameters take the first slots of the frame, in order
+                let scope = Scope::frame(captured, bound.into_iter().chain(arguments).collect());
+                if tail {
+                    let caller = self.frames.pop().unwrap();
+                    while self.stack.top > caller.base {
+                        self.discard();
+                    }
+                    self.frames.push(Frame { prototype, ip: 0, scope, base: caller.base, call: caller.call });
+                } else {
+                    self.frames.push(Frame { prototype, ip: 0, scope, base: self.stack.top, call: Some(Call::enter()?) });
+                }
+                Ok(())
+            },
+            function => {
+                let scope = self.frame().scope.clone();
+                let result = FunctionTypes::call(&function, functionObject.clone(), arguments, scope)?;
+                self.push(result)
+            }
+        }
+    }
+
+    /// the two operands on top of the stack, worked out on the spot when they are numbers, integers or bools
+    /// and the operator's name still holds its builtin, and handed to whatever the name holds otherwise
+    fn operate(&mut self, operator: Operator) -> RavenResult<()>{
+        let scope = self.frame().scope.clone();
+        let function = match stdlib::replaced() {
+            true => Some(scope.borrow().get(operator.builtin())?.get()),
+            false => None,
+        };
+        let builtin = function.as_ref().is_none_or(|function| stdlib::isOperator(operator.builtin(), &function.borrow()));
+        let (x, y) = (*self.stack.below(1), *self.stack.below(0));
+        let (x, y) = (self.scalar(x), self.scalar(y));
+        if let (Some(x), Some(y), true) = (&x, &y, builtin) {
+            let pair = numeric(x, y);
+            let order = |test: fn(Ordering) -> bool| pair.as_ref().map(|pair| Type::Bool(pair.order().is_some_and(test)));
+            let result = match (operator, (x, y)) {
+                (Operator::Equals, _) => Some(Type::Bool(equal(x, y))),
+                (Operator::NotEquals, _) => Some(Type::Bool(!equal(x, y))),
+                (Operator::And, (Type::Bool(x), Type::Bool(y))) => Some(Type::Bool(*x && *y)),
+                (Operator::Or, (Type::Bool(x), Type::Bool(y))) => Some(Type::Bool(*x || *y)),
+                (Operator::Less, _) => order(Ordering::is_lt),
+                (Operator::LessOrEqual, _) => order(Ordering::is_le),
+                (Operator::Greater, _) => order(Ordering::is_gt),
+                (Operator::GreaterOrEqual, _) => order(Ordering::is_ge),
+                (Operator::Add, _) => pair.map(|pair| pair.add()).transpose()?,
+                (Operator::Subtract, _) => pair.map(|pair| pair.subtract()).transpose()?,
+                (Operator::Multiply, _) => pair.map(|pair| pair.multiply()).transpose()?,
+                (Operator::Divide, _) => pair.map(|pair| pair.divide()).transpose()?,
+                (Operator::Power, _) => pair.map(|pair| pair.power()).transpose()?,
+                (Operator::Modulo, _) => pair.map(|pair| pair.modulo()).transpose()?,
+                _ => None,
+            };
+            if let Some(result) = result {
+                self.discard();
+                self.discard();
+                let word = match inline(&result) {
+                    Some(word) => word,
+                    None => word(TAG_HEAP, self.heap.alloc(result.wrap())),
+                };
+                return self.stack.push(word)
+            }
+        }
+        // the builtin gives the error for operands it does not take, or does what it was replaced with
+        let y = self.pop().ok_or_else(|| RavenError::VoidValue("an argument".to_string()))?;
+        let x = self.pop().ok_or_else(|| RavenError::VoidValue("an argument".to_string()))?;
+        let function = match function {
+            Some(function) => function,
+            None => scope.borrow().get(operator.builtin())?.get(),
+        };
+        let result = match &*function.borrow() {
+            Type::Function(callee) => FunctionTypes::call(callee, function.clone(), vec![x, y], scope.clone())?,
+            _ => return Err(RavenError::NotCallable(operator.builtin().to_string())),
+        };
+        self.push(result)
+    }
+
+    /// the compiler always loads the callee right before calling it
+    fn calleeName(&self) -> Option<String>{
+        let frame = self.frame();
+        match frame.prototype.chunk.code[frame.ip.checked_sub(2)?] {
+            Op::Load(name) => Some(frame.prototype.chunk.names[name as usize].clone()),
+            _ => None
+        }
+    }
+
+    fn push(&mut self, value: Option<Object>) -> RavenResult<()>{
+        let word = match value {
+            None => word(TAG_VOID, 0),
+            Some(object) => match inline(&object.borrow()) {
+                Some(word) => word,
+                None => word(TAG_HEAP, self.heap.alloc(object.clone())),
+            }
+        };
+        self.stack.push(word)
+    }
+
+    /// pops a word without making an object of what it holds
+    fn discard(&mut self){
+        let word = self.stack.pop();
+        if tag(word) == TAG_HEAP {
+            self.heap.take(payload(word));
+        }
+    }
+
+    /// the number, integer or bool a word holds, inline or on the heap
+    fn scalar(&self, word: u64) -> Option<Type>{
+        match tag(word) {
+            TAG_NUMBER => Some(Type::Number(f32::from_bits(payload(word)))),
+            TAG_BOOL => Some(Type::Bool(payload(word) != 0)),
+            TAG_INTEGER => Some(Type::Integer(payload(word) as i32 as i64)),
+            TAG_HEAP => match &*self.heap.objects[payload(word) as usize].as_ref()?.borrow() {
+                value @ (Type::Number(_) | Type::Bool(_) | Type::Integer(_)) => Some(value.clone()),
+                _ => None
+            },
+            _ => None
+        }
+    }
+
+    fn pop(&mut self) -> Option<Object>{
+        let word = self.stack.pop();
+        self.release(word)
+    }
+
+    fn release(&mut self, word: u64) -> Option<Object>{
+        match tag(word) {
+            TAG_NUMBER => Some(Type::Number(f32::from_bits(payload(word))).wrap()),
+            TAG_BOOL => Some(Type::Bool(payload(word) != 0).wrap()),
+            TAG_INTEGER => Some(Type::Integer(payload(word) as i32 as i64).wrap()),
+            TAG_HEAP => Some(self.heap.take(payload(word))),
+            _ => None
+        }
+    }
+}
+
+/// the word for a value that fits in one, without allocating an object for it
+fn inline(value: &Type) -> Option<u64>{
+    match value {
+        Type::Number(n) => Some(word(TAG_NUMBER, n.to_bits())),
+        Type::Bool(b) => Some(word(TAG_BOOL, *b as u32)),
+        Type::Integer(i) if i32::try_from(*i).is_ok() => Some(word(TAG_INTEGER, *i as i32 as u32)),
+        _ => None
+    }
+}
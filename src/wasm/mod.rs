@@ -1 +1 @@
-mod wasm;
\ No newline at end of file
+pub mod wasm;
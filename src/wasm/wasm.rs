@@ -1 +1,123 @@
-// #![cfg(target_arch = "wasm32")]
+use std::{cell::RefCell, rc::Rc};
+
+use crate::interpreter::depth::WASM_MAX_DEPTH;
+use crate::interpreter::interpreter::Interpreter;
+
+/// what running a script on the web gives back to the page
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output{
+    /// everything the script printed, one line per `print`
+    pub stdout: String,
+    /// the value of the last line, unless it was a statement or the script failed
+    pub result: Option<String>,
+    /// the error that stopped the script, rendered like the command line does
+    pub error: Option<String>,
+}
+
+impl Output{
+    /// `{"stdout": ..., "result": ..., "error": ...}`, missing fields are `null`
+    pub fn toJson(&self) -> String{
+        let field = |value: &Option<String>| value.as_deref().map_or("null".to_string(), json);
+        format!("{{\"stdout\":{},\"result\":{},\"error\":{}}}", json(&self.stdout), field(&self.result), field(&self.error))
+    }
+}
+
+fn json(text: &str) -> String{
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// runs `source` with the standard library in a fresh interpreter, collecting what it prints instead of
+/// writing it to stdout, which a page does not have
+pub fn run(source: &str) -> Output{
+    let stdout = Rc::new(RefCell::new(String::new()));
+    let mut i = Interpreter::with_stdlib();
+    i.set_max_depth(WASM_MAX_DEPTH);
+    let out = stdout.clone();
+    i.addFunction("print", 1, move |_, args|{
+        let text = args.into_iter().map(|e| e.borrow().toString()).collect::<Vec<_>>().join(" ");
+        let mut out = out.borrow_mut();
+        out.push_str(&text);
+        out.push('\n');
+        Ok(None)
+    });
+
+    let (result, error) = match i.eval(source) {
+        Ok(value) => (value.object().map(|value| value.borrow().quoted()), None),
+        Err(error) => (None, Some(error.render(source, "<input>"))),
+    };
+    let stdout = stdout.borrow().clone();
+    Output { stdout, result, error }
+}
+
+/// the functions the module exports, for `docs/raven.js`. the page copies the source into memory from
+/// `raven_alloc`, calls `raven_run` and reads `raven_output_len` bytes of json from the pointer it returns
+#[cfg(target_arch = "wasm32")]
+mod exports{
+    use std::cell::RefCell;
+
+    thread_local!{
+        /// the json of the last run, kept until the next one so the page can read it
+        static OUTPUT: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    }
+
+    #[link(wasm_import_module = "env")]
+    extern "C" {
+        /// `Math.random`, supplied by the page
+        fn raven_random() -> f64;
+    }
+
+    fn random(bytes: &mut [u8]) -> Result<(), getrandom::Error>{
+        for byte in bytes {
+            *byte = (unsafe { raven_random() } * 256.0) as u8;
+        }
+        Ok(())
+    }
+    getrandom::register_custom_getrandom!(random);
+
+    #[no_mangle]
+    pub extern "C" fn raven_alloc(len: usize) -> *mut u8{
+        let mut buffer = Vec::<u8>::with_capacity(len);
+        let pointer = buffer.as_mut_ptr();
+        std::mem::forget(buffer);
+        pointer
+    }
+
+    /// # Safety
+    /// `pointer` and `len` must come from one call to `raven_alloc`
+    #[no_mangle]
+    pub unsafe extern "C" fn raven_free(pointer: *mut u8, len: usize){
+        drop(Vec::from_raw_parts(pointer, 0, len));
+    }
+
+    /// # Safety
+    /// `source` must point at `len` bytes of memory the page wrote the script into
+    #[no_mangle]
+    pub unsafe extern "C" fn raven_run(source: *const u8, len: usize) -> *const u8{
+        let source = String::from_utf8_lossy(std::slice::from_raw_parts(source, len));
+        // the page shows errors as plain text
+        crate::color::disable();
+        let json = super::run(&source).toJson();
+        OUTPUT.with(|output| {
+            *output.borrow_mut() = json.into_bytes();
+            output.borrow().as_ptr()
+        })
+    }
+
+    #[no_mangle]
+    pub extern "C" fn raven_output_len() -> usize{
+        OUTPUT.with(|output| output.borrow().len())
+    }
+}